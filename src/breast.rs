@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono::{self,NaiveDate};
+
+use super::{aggregate,duration_serialize,FeedingEvent,Period,Timestamp};
+
+/// A breast.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Serialize)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn other(&self) -> Side {
+        match *self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Side::Left => write!(f, "left"),
+            Side::Right => write!(f, "right"),
+        }
+    }
+}
+
+// The side and duration of a breast feed, or None for bottles.
+fn breast(fe: &FeedingEvent) -> Option<(Side, chrono::Duration)> {
+    match *fe {
+        FeedingEvent::Bottle(_) => None,
+        FeedingEvent::LeftBreast(ref be) => Some((Side::Left, be.duration)),
+        FeedingEvent::RightBreast(ref be) => Some((Side::Right, be.duration)),
+    }
+}
+
+/// The time spent feeding from each side over a day or a period.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct SideBalance {
+    /// The date, or the label of the period, e.g. `2018-W09`.
+    pub label: String,
+    #[serde(serialize_with = "duration_serialize")]
+    pub left: chrono::Duration,
+    #[serde(serialize_with = "duration_serialize")]
+    pub right: chrono::Duration,
+    /// `(left - right) / (left + right)`, from -1 for only right to 1 for
+    /// only left. `None` when neither side was used.
+    pub imbalance: Option<f64>,
+}
+
+impl SideBalance {
+    fn new(label: String, left: chrono::Duration, right: chrono::Duration) -> SideBalance {
+        let total = (left + right).num_seconds() as f64;
+        SideBalance{
+            label,
+            left,
+            right,
+            imbalance: if total > 0.0 { Some((left - right).num_seconds() as f64 / total) } else { None },
+        }
+    }
+}
+
+// Totals each side's feeding time by date.
+fn daily_sides(events: &[FeedingEvent]) -> BTreeMap<NaiveDate, (chrono::Duration, chrono::Duration)> {
+    let mut days = BTreeMap::new();
+    for fe in events {
+        if let Some((side, duration)) = breast(fe) {
+            let day = days.entry(fe.time().date_naive()).or_insert((chrono::Duration::zero(), chrono::Duration::zero()));
+            match side {
+                Side::Left => day.0 += duration,
+                Side::Right => day.1 += duration,
+            }
+        }
+    }
+    days
+}
+
+/// Totals each side's feeding time by date.
+pub fn side_balance_by_date(events: &[FeedingEvent]) -> Vec<SideBalance> {
+    daily_sides(events).into_iter().map(|(date, (left, right))| {
+        SideBalance::new(date.to_string(), left, right)
+    }).collect()
+}
+
+/// Totals each side's feeding time by period.
+pub fn side_balance_by_period(events: &[FeedingEvent], period: &Period) -> Vec<SideBalance> {
+    let days = daily_sides(events);
+    let left: BTreeMap<NaiveDate, f64> = days.iter().map(|(&d, &(l, _))| (d, l.num_seconds() as f64)).collect();
+    let right: BTreeMap<NaiveDate, f64> = days.iter().map(|(&d, &(_, r))| (d, r.num_seconds() as f64)).collect();
+    aggregate(&left, period).into_iter().zip(aggregate(&right, period)).map(|(l, r)| {
+        SideBalance::new(l.label, chrono::Duration::seconds(l.total as i64), chrono::Duration::seconds(r.total as i64))
+    }).collect()
+}
+
+/// Settings for suggesting which side to offer next.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct NextSideConfig {
+    /// How far back to look when judging the balance between sides.
+    pub window: chrono::Duration,
+    /// The size of imbalance, between 0 and 1, above which the less used
+    /// side is offered rather than alternating.
+    pub threshold: f64,
+}
+
+impl Default for NextSideConfig {
+    fn default() -> NextSideConfig {
+        NextSideConfig{
+            window: chrono::Duration::hours(24),
+            threshold: 0.2,
+        }
+    }
+}
+
+/// The side to offer at the next feed, and why.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct NextSide {
+    pub side: Side,
+    /// The side the last breast feed ended on.
+    pub last_side: Side,
+    /// When the last breast feed started.
+    pub last_time: Timestamp,
+    /// Each side's feeding time over the configured window up to the last
+    /// feed.
+    #[serde(serialize_with = "duration_serialize")]
+    pub recent_left: chrono::Duration,
+    #[serde(serialize_with = "duration_serialize")]
+    pub recent_right: chrono::Duration,
+    pub imbalance: Option<f64>,
+    /// Whether `side` was chosen to correct an imbalance rather than to
+    /// alternate.
+    pub rebalancing: bool,
+}
+
+/// The side and start time of the last breast feed.
+pub fn last_side(events: &[FeedingEvent]) -> Option<(Side, Timestamp)> {
+    events.iter().
+        filter_map(|fe| breast(fe).map(|(side, _)| (side, fe.time()))).
+        max_by_key(|&(_, time)| time)
+}
+
+/// Suggests the side to offer next: the opposite of the last side used,
+/// unless the recent imbalance calls for the less used side.
+pub fn next_side(events: &[FeedingEvent], config: &NextSideConfig) -> Option<NextSide> {
+    let (last_side, last_time) = last_side(events)?;
+    let since = last_time - config.window;
+    let recent: Vec<FeedingEvent> = events.iter().
+        filter(|fe| fe.time() > since && fe.time() <= last_time).
+        cloned().
+        collect();
+    let (left, right) = daily_sides(&recent).values().
+        fold((chrono::Duration::zero(), chrono::Duration::zero()), |(l, r), &(dl, dr)| (l + dl, r + dr));
+    let recent = SideBalance::new(String::new(), left, right);
+
+    let imbalance = recent.imbalance.unwrap_or(0.0);
+    let less_used = if imbalance > 0.0 { Side::Right } else { Side::Left };
+    let rebalancing = imbalance.abs() > config.threshold && less_used == last_side;
+    Some(NextSide{
+        side: if rebalancing { last_side } else { last_side.other() },
+        last_side,
+        last_time,
+        recent_left: left,
+        recent_right: right,
+        imbalance: recent.imbalance,
+        rebalancing,
+    })
+}
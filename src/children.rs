@@ -0,0 +1,72 @@
+use std::collections::{BTreeMap,BTreeSet};
+
+use chrono::NaiveDate;
+
+use super::{Event,Metric,UnitSystem};
+
+/// An event along with the child it was recorded for.
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct ChildEvent {
+    /// The child named in the export's `Child` column, or the reader's
+    /// default child. `None` when neither was given.
+    pub child: Option<String>,
+    pub event: Event,
+}
+
+/// The children events were recorded for, in order.
+pub fn children(events: &[ChildEvent]) -> Vec<String> {
+    let names: BTreeSet<&String> = events.iter().filter_map(|ce| ce.child.as_ref()).collect();
+    names.into_iter().cloned().collect()
+}
+
+/// Keeps the events recorded for `child`, along with events that weren't
+/// recorded for any child in particular.
+pub fn filter_child<I>(events: I, child: &str) -> Vec<Event>
+    where I: IntoIterator<Item=ChildEvent> {
+    events.into_iter().
+        filter(|ce| ce.child.as_ref().is_none_or(|c| c == child)).
+        map(|ce| ce.event).
+        collect()
+}
+
+/// Groups events by child, keeping the order they were given in. Events not
+/// recorded for any child are grouped under `None`.
+pub fn group_by_child<I>(events: I) -> BTreeMap<Option<String>, Vec<Event>>
+    where I: IntoIterator<Item=ChildEvent> {
+    let mut groups: BTreeMap<Option<String>, Vec<Event>> = BTreeMap::new();
+    for ce in events {
+        groups.entry(ce.child).or_default().push(ce.event);
+    }
+    groups
+}
+
+/// A metric's value for each child on one date.
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct ComparisonRow {
+    pub date: NaiveDate,
+    /// The value for each of `ChildComparison::children`, in order.
+    pub values: Vec<Option<f64>>,
+}
+
+/// A daily metric for several children side by side.
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct ChildComparison {
+    pub children: Vec<String>,
+    /// One row for each date any child has a value on.
+    pub rows: Vec<ComparisonRow>,
+}
+
+/// Compares a daily metric between children, given each child's events.
+pub fn compare_children(events: &BTreeMap<String, Vec<Event>>, metric: Metric, units: UnitSystem) -> ChildComparison {
+    let series: Vec<BTreeMap<NaiveDate, f64>> = events.values().map(|events| metric.daily_series(events, units)).collect();
+    let dates: BTreeSet<NaiveDate> = series.iter().flat_map(|s| s.keys().cloned()).collect();
+    ChildComparison{
+        children: events.keys().cloned().collect(),
+        rows: dates.into_iter().map(|date| {
+            ComparisonRow{
+                date,
+                values: series.iter().map(|s| s.get(&date).cloned()).collect(),
+            }
+        }).collect(),
+    }
+}
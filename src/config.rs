@@ -0,0 +1,251 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use csv;
+
+use super::{DstPolicy,Error,TimeZoneSpec};
+
+/// The order of the day, month and year in exported dates.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum DateOrder {
+    /// `31/12/2018`, Baby Manager's default.
+    #[default]
+    DayMonthYear,
+    /// `12/31/2018`, used by phones set to a US locale.
+    MonthDayYear,
+    /// `2018/12/31`.
+    YearMonthDay,
+}
+
+impl FromStr for DateOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<DateOrder, String> {
+        match s {
+            "dmy" => Ok(DateOrder::DayMonthYear),
+            "mdy" => Ok(DateOrder::MonthDayYear),
+            "ymd" => Ok(DateOrder::YearMonthDay),
+            _ => Err(format!("unknown date order {}; expecting dmy, mdy or ymd", s)),
+        }
+    }
+}
+
+/// Whether exported times use a 24-hour or a 12-hour clock.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum Clock {
+    /// `18:30`.
+    #[default]
+    TwentyFourHour,
+    /// `6:30 PM`.
+    TwelveHour,
+}
+
+impl FromStr for Clock {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Clock, String> {
+        match s {
+            "24h" => Ok(Clock::TwentyFourHour),
+            "12h" => Ok(Clock::TwelveHour),
+            _ => Err(format!("unknown clock {}; expecting 24h or 12h", s)),
+        }
+    }
+}
+
+/// The units quantities and measurements are recorded in.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum UnitSystem {
+    /// Ounces, pounds and inches.
+    #[default]
+    Imperial,
+    /// Millilitres, kilograms and centimetres.
+    Metric,
+}
+
+impl FromStr for UnitSystem {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<UnitSystem, String> {
+        match s {
+            "imperial" => Ok(UnitSystem::Imperial),
+            "metric" => Ok(UnitSystem::Metric),
+            _ => Err(format!("unknown unit system {}; expecting imperial or metric", s)),
+        }
+    }
+}
+
+/// Describes the locale specific formats used by an export.
+///
+/// The defaults match an export from a phone set to a UK locale, which is
+/// what Baby Manager produces out of the box.
+#[derive(Debug,Clone,Copy,PartialEq,Default)]
+pub struct ImportConfig {
+    pub date_order: DateOrder,
+    pub clock: Clock,
+    /// The character separating the integer and fractional part of numbers.
+    pub decimal_separator: DecimalSeparator,
+    pub units: UnitSystem,
+    pub timezone: TimeZoneSpec,
+    pub dst_policy: DstPolicy,
+}
+
+/// The decimal separator used by numbers in an export.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum DecimalSeparator {
+    /// `1.5`
+    #[default]
+    Point,
+    /// `1,5`
+    Comma,
+}
+
+impl FromStr for DecimalSeparator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<DecimalSeparator, String> {
+        match s {
+            "point" | "." => Ok(DecimalSeparator::Point),
+            "comma" | "," => Ok(DecimalSeparator::Comma),
+            _ => Err(format!("unknown decimal separator {}; expecting point or comma", s)),
+        }
+    }
+}
+
+impl ImportConfig {
+    /// The `chrono` format string matching this configuration's timestamps.
+    pub(crate) fn timestamp_format(&self) -> &'static str {
+        match (self.date_order, self.clock) {
+            (DateOrder::DayMonthYear, Clock::TwentyFourHour) => "%d/%m/%Y %H:%M",
+            (DateOrder::DayMonthYear, Clock::TwelveHour) => "%d/%m/%Y %I:%M %p",
+            (DateOrder::MonthDayYear, Clock::TwentyFourHour) => "%m/%d/%Y %H:%M",
+            (DateOrder::MonthDayYear, Clock::TwelveHour) => "%m/%d/%Y %I:%M %p",
+            (DateOrder::YearMonthDay, Clock::TwentyFourHour) => "%Y/%m/%d %H:%M",
+            (DateOrder::YearMonthDay, Clock::TwelveHour) => "%Y/%m/%d %I:%M %p",
+        }
+    }
+
+    /// Rewrites decimal commas in `s` as decimal points.
+    pub(crate) fn normalize_decimals<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        lazy_static! {
+            static ref COMMA_RE: ::regex::Regex = ::regex::Regex::new(r"(\d),(\d)").unwrap();
+        }
+        match self.decimal_separator {
+            DecimalSeparator::Point => Cow::Borrowed(s),
+            DecimalSeparator::Comma => COMMA_RE.replace_all(s, "$1.$2"),
+        }
+    }
+
+    /// Guesses the configuration from the first `rows` rows of an export.
+    ///
+    /// Fields that can't be determined from the sample keep their default
+    /// values. The timezone and DST policy are never detected. Malformed
+    /// rows are left out of the sample.
+    pub fn detect<R: io::Read>(rdr: R, rows: usize) -> Result<ImportConfig, Error> {
+        let mut rdr = csv::ReaderBuilder::new().flexible(true).from_reader(rdr);
+        let headers = rdr.headers()?.clone();
+        let column = |name: &str| headers.iter().position(|h| h == name);
+        let (start, end, extra) = (column("Start"), column("End"), column("Extra"));
+        let mut detector = Detector::default();
+        for record in rdr.records().take(rows) {
+            let record = match record {
+                Ok(record) => record,
+                Err(ref err) if !err.is_io_error() => continue,
+                Err(err) => return Err(Error::from(err)),
+            };
+            for idx in [start, end].iter().filter_map(|&i| i) {
+                if let Some(ts) = record.get(idx) {
+                    detector.timestamp(ts);
+                }
+            }
+            if let Some(text) = extra.and_then(|i| record.get(i)) {
+                detector.quantity(text);
+            }
+        }
+        Ok(detector.config())
+    }
+
+    /// Like `detect`, reading the sample from a file.
+    pub fn detect_from_path<P: AsRef<Path>>(path: P, rows: usize) -> Result<ImportConfig, Error> {
+        let file = File::open(path).map_err(csv::Error::from)?;
+        ImportConfig::detect(file, rows)
+    }
+}
+
+// Tallies evidence for each format seen in a sample of rows.
+#[derive(Default)]
+struct Detector {
+    day_first: usize,
+    month_first: usize,
+    year_first: usize,
+    twelve_hour: usize,
+    comma: usize,
+    metric: usize,
+    imperial: usize,
+}
+
+impl Detector {
+    fn timestamp(&mut self, ts: &str) {
+        let ts = ts.trim();
+        if ts.is_empty() {
+            return;
+        }
+        let upper = ts.to_uppercase();
+        if upper.ends_with("AM") || upper.ends_with("PM") {
+            self.twelve_hour += 1;
+        }
+        let date = ts.split_whitespace().next().unwrap_or("");
+        let parts: Vec<&str> = date.split(['/', '-', '.']).collect();
+        if parts.len() != 3 {
+            return;
+        }
+        if parts[0].len() == 4 {
+            self.year_first += 1;
+            return;
+        }
+        let first: u32 = parts[0].parse().unwrap_or(0);
+        let second: u32 = parts[1].parse().unwrap_or(0);
+        if first > 12 {
+            self.day_first += 1;
+        } else if second > 12 {
+            self.month_first += 1;
+        }
+    }
+
+    fn quantity(&mut self, text: &str) {
+        lazy_static! {
+            static ref COMMA_RE: ::regex::Regex = ::regex::Regex::new(r"\d,\d").unwrap();
+        }
+        for word in text.split(|c: char| !c.is_alphabetic()) {
+            match word {
+                "ml" | "kg" | "cm" => self.metric += 1,
+                "oz" | "lb" | "in" => self.imperial += 1,
+                _ => {},
+            }
+        }
+        if COMMA_RE.is_match(text) {
+            self.comma += 1;
+        }
+    }
+
+    fn config(&self) -> ImportConfig {
+        let mut config = ImportConfig::default();
+        if self.year_first > 0 {
+            config.date_order = DateOrder::YearMonthDay;
+        } else if self.month_first > self.day_first {
+            config.date_order = DateOrder::MonthDayYear;
+        }
+        if self.twelve_hour > 0 {
+            config.clock = Clock::TwelveHour;
+        }
+        if self.comma > 0 {
+            config.decimal_separator = DecimalSeparator::Comma;
+        }
+        if self.metric > self.imperial {
+            config.units = UnitSystem::Metric;
+        }
+        config
+    }
+}
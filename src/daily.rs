@@ -0,0 +1,34 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+
+use super::Event;
+
+/// An inclusive range of dates. Either end may be left open.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub struct DateRange {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl DateRange {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.from.is_none_or(|from| date >= from) && self.to.is_none_or(|to| date <= to)
+    }
+
+    /// Keeps the events whose time falls within the range.
+    pub fn filter(&self, events: Vec<Event>) -> Vec<Event> {
+        events.into_iter().filter(|e| self.contains(e.time().date_naive())).collect()
+    }
+}
+
+/// Groups `items` by the date `date` returns for each, keeping the order
+/// they were given in within each date.
+pub fn group_by_date<T, I, F>(items: I, date: F) -> BTreeMap<NaiveDate, Vec<T>>
+    where I: IntoIterator<Item=T>, F: Fn(&T) -> NaiveDate {
+    let mut groups: BTreeMap<NaiveDate, Vec<T>> = BTreeMap::new();
+    for item in items {
+        groups.entry(date(&item)).or_default().push(item);
+    }
+    groups
+}
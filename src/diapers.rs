@@ -0,0 +1,116 @@
+use chrono::{self,NaiveDate};
+
+use super::{group_by_date,option_duration_serialize,DiaperEvent,Timestamp};
+
+/// Diaper changes on one date.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct DiaperDay {
+    pub date: NaiveDate,
+    pub changes: usize,
+    pub wet: usize,
+    pub dirty: usize,
+    /// The longest time between wet diapers, for gaps ending on this date.
+    #[serde(serialize_with = "option_duration_serialize")]
+    pub longest_wet_gap: Option<chrono::Duration>,
+}
+
+/// Counts diaper changes by date.
+pub fn diapers_by_date(diapers: &[DiaperEvent]) -> Vec<DiaperDay> {
+    let mut wet: Vec<Timestamp> = diapers.iter().filter(|de| de.pee).map(|de| de.time).collect();
+    wet.sort();
+    let gaps = group_by_date(wet.windows(2).map(|w| (w[1], w[1] - w[0])), |&(end, _)| end.date_naive());
+    group_by_date(diapers, |de| de.time.date_naive()).into_iter().map(|(date, diapers)| {
+        DiaperDay{
+            date,
+            changes: diapers.len(),
+            wet: diapers.iter().filter(|de| de.pee).count(),
+            dirty: diapers.iter().filter(|de| de.poo).count(),
+            longest_wet_gap: gaps.get(&date).and_then(|gaps| gaps.iter().map(|&(_, gap)| gap).max()),
+        }
+    }).collect()
+}
+
+/// Thresholds for diaper alerts.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct DiaperConfig {
+    /// The length of the window wet diapers are counted over.
+    pub window: chrono::Duration,
+    /// The fewest wet diapers expected within `window`.
+    pub min_wet: usize,
+    /// The longest expected time without a dirty diaper.
+    pub max_without_stool: chrono::Duration,
+}
+
+impl Default for DiaperConfig {
+    fn default() -> DiaperConfig {
+        DiaperConfig{
+            window: chrono::Duration::hours(24),
+            min_wet: 6,
+            max_without_stool: chrono::Duration::days(3),
+        }
+    }
+}
+
+/// What a diaper alert is about.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Serialize)]
+pub enum DiaperAlertKind {
+    /// Fewer wet diapers than expected, a sign of dehydration.
+    FewWet,
+    /// No dirty diaper for longer than expected, a sign of constipation.
+    NoStool,
+}
+
+/// A period during which diaper output fell short of `DiaperConfig`.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct DiaperAlert {
+    pub kind: DiaperAlertKind,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    /// For `FewWet`, the fewest wet diapers in any window of the period.
+    pub count: usize,
+}
+
+/// Finds periods with too few wet diapers or without a dirty one, in order
+/// of their start. Windows of wet diapers are checked up to each change, and
+/// only once a full window of records is available. Overlapping windows
+/// are reported as one alert.
+pub fn diaper_alerts(diapers: &[DiaperEvent], config: &DiaperConfig) -> Vec<DiaperAlert> {
+    let mut times: Vec<Timestamp> = diapers.iter().map(|de| de.time).collect();
+    times.sort();
+    let (first, last) = match (times.first(), times.last()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return Vec::new(),
+    };
+    let wet: Vec<Timestamp> = diapers.iter().filter(|de| de.pee).map(|de| de.time).collect();
+    let mut dirty: Vec<Timestamp> = diapers.iter().filter(|de| de.poo).map(|de| de.time).collect();
+    dirty.sort();
+
+    let mut alerts: Vec<DiaperAlert> = Vec::new();
+    for &end in &times {
+        let start = end - config.window;
+        if start < first {
+            continue;
+        }
+        let count = wet.iter().filter(|&&t| t >= start && t < end).count();
+        if count >= config.min_wet {
+            continue;
+        }
+        match alerts.last_mut() {
+            Some(alert) if start <= alert.end => {
+                alert.end = end;
+                alert.count = alert.count.min(count);
+            },
+            _ => alerts.push(DiaperAlert{kind: DiaperAlertKind::FewWet, start, end, count}),
+        }
+    }
+
+    let mut since = first;
+    for &end in dirty.iter().chain(Some(&last)) {
+        if end - since > config.max_without_stool {
+            alerts.push(DiaperAlert{kind: DiaperAlertKind::NoStool, start: since, end, count: 0});
+        }
+        since = end;
+    }
+    alerts.sort_by_key(|a| a.start);
+    alerts
+}
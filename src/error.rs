@@ -0,0 +1,88 @@
+use std::error;
+use std::fmt;
+
+use csv;
+
+/// The location and raw contents of a CSV field that failed to parse.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Field {
+    /// 1-based line number within the CSV input.
+    pub line: u64,
+    /// Name of the column the text was read from, e.g. `"Start"`.
+    pub column: &'static str,
+    /// The text that could not be parsed.
+    pub text: String,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {:?}", self.line, self.column, self.text)
+    }
+}
+
+/// Errors produced while reading Baby Manager data.
+#[derive(Debug)]
+pub enum Error {
+    /// The underlying CSV reader failed, either on I/O or on malformed CSV.
+    Csv(csv::Error),
+    /// The `Type` column holds a value we don't know how to interpret.
+    UnknownType(Field),
+    /// A timestamp doesn't match the configured date and time format.
+    BadTimestamp(Field),
+    /// A duration isn't in the expected `HH:MM` format.
+    BadDuration(Field),
+    /// A quantity such as `"1.5 oz"` couldn't be parsed.
+    BadQuantity(Field),
+    /// A measurement such as `"Weight: 7.5 lb"` couldn't be parsed.
+    BadMeasurement(Field),
+}
+
+impl Error {
+    /// The field that failed to parse, if the error relates to a single field.
+    pub fn field(&self) -> Option<&Field> {
+        match *self {
+            Error::Csv(_) => None,
+            Error::UnknownType(ref f) |
+            Error::BadTimestamp(ref f) |
+            Error::BadDuration(ref f) |
+            Error::BadQuantity(ref f) |
+            Error::BadMeasurement(ref f) => Some(f),
+        }
+    }
+
+    /// The CSV line number the error occurred on, when known.
+    pub fn line(&self) -> Option<u64> {
+        match *self {
+            Error::Csv(ref e) => e.position().map(|p| p.line()),
+            _ => self.field().map(|f| f.line),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Csv(ref e) => write!(f, "csv error: {}", e),
+            Error::UnknownType(ref field) => write!(f, "unknown event type at {}", field),
+            Error::BadTimestamp(ref field) => write!(f, "bad timestamp at {}", field),
+            Error::BadDuration(ref field) => write!(f, "bad duration at {}; expecting HH:MM", field),
+            Error::BadQuantity(ref field) => write!(f, "bad quantity at {}", field),
+            Error::BadMeasurement(ref field) => write!(f, "bad measurement at {}", field),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Csv(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(e: csv::Error) -> Error {
+        Error::Csv(e)
+    }
+}
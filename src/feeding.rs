@@ -0,0 +1,195 @@
+use chrono::{self,NaiveDate,Timelike};
+
+use super::{duration_serialize,group_by_date,option_duration_serialize,FeedingEvent,Timestamp,Volume};
+
+/// Settings for grouping and analyzing feeds.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct FeedingConfig {
+    /// Feeding events starting within this of the previous one ending, e.g.
+    /// switching sides, are counted as one feed.
+    pub same_feed: chrono::Duration,
+    /// The number of feeds within `cluster_window` that make up a cluster.
+    pub cluster_feeds: usize,
+    pub cluster_window: chrono::Duration,
+}
+
+impl Default for FeedingConfig {
+    fn default() -> FeedingConfig {
+        FeedingConfig{
+            same_feed: chrono::Duration::minutes(10),
+            cluster_feeds: 5,
+            cluster_window: chrono::Duration::hours(3),
+        }
+    }
+}
+
+/// One feed, made up of the bottles and breasts given in one sitting.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct Feed {
+    pub events: Vec<FeedingEvent>,
+}
+
+impl Feed {
+    pub fn start(&self) -> Timestamp {
+        self.events[0].time()
+    }
+
+    pub fn end(&self) -> Timestamp {
+        self.events.iter().map(event_end).max().unwrap()
+    }
+}
+
+fn event_end(fe: &FeedingEvent) -> Timestamp {
+    match *fe {
+        FeedingEvent::Bottle(ref be) => be.time,
+        FeedingEvent::LeftBreast(ref be) |
+        FeedingEvent::RightBreast(ref be) => be.end.unwrap_or(be.start + be.duration),
+    }
+}
+
+/// Groups feeding events into feeds, in time order.
+pub fn feeds(events: &[FeedingEvent], config: &FeedingConfig) -> Vec<Feed> {
+    let mut events = events.to_vec();
+    events.sort_by_key(|fe| fe.time());
+    let mut feeds: Vec<Feed> = Vec::new();
+    for fe in events {
+        if let Some(feed) = feeds.last_mut() {
+            if fe.time().signed_duration_since(feed.end()) <= config.same_feed {
+                feed.events.push(fe);
+                continue;
+            }
+        }
+        feeds.push(Feed{events: vec![fe]});
+    }
+    feeds
+}
+
+/// The time from the start of one feed to the start of the next.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct FeedInterval {
+    pub from: Timestamp,
+    pub to: Timestamp,
+    #[serde(serialize_with = "duration_serialize")]
+    pub interval: chrono::Duration,
+}
+
+pub fn feed_intervals(feeds: &[Feed]) -> Vec<FeedInterval> {
+    feeds.windows(2).map(|w| {
+        FeedInterval{
+            from: w[0].start(),
+            to: w[1].start(),
+            interval: w[1].start().signed_duration_since(w[0].start()),
+        }
+    }).collect()
+}
+
+/// The mean interval between feeds starting within a range of hours.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct TimeOfDayIntervals {
+    /// The first hour of the range, from 0 to 23.
+    pub start_hour: u32,
+    /// The hour after the end of the range, from 1 to 24.
+    pub end_hour: u32,
+    pub intervals: usize,
+    #[serde(serialize_with = "option_duration_serialize")]
+    pub mean: Option<chrono::Duration>,
+}
+
+/// Averages intervals by the time of day of the feed they start from, in
+/// ranges of `hours` hours.
+pub fn intervals_by_time_of_day(intervals: &[FeedInterval], hours: u32) -> Vec<TimeOfDayIntervals> {
+    let hours = hours.clamp(1, 24);
+    (0..24).step_by(hours as usize).map(|start_hour| {
+        let end_hour = (start_hour + hours).min(24);
+        let matching: Vec<&FeedInterval> = intervals.iter().
+            filter(|fi| fi.from.hour() >= start_hour && fi.from.hour() < end_hour).
+            collect();
+        let total = matching.iter().fold(chrono::Duration::zero(), |a, fi| a + fi.interval);
+        TimeOfDayIntervals{
+            start_hour,
+            end_hour,
+            intervals: matching.len(),
+            mean: if matching.is_empty() { None } else { Some(total / matching.len() as i32) },
+        }
+    }).collect()
+}
+
+/// A run of feeds close together, as configured by `FeedingConfig`.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct ClusterFeeding {
+    pub start: Timestamp,
+    /// The start of the last feed in the cluster.
+    pub end: Timestamp,
+    pub feeds: usize,
+}
+
+/// Finds cluster feeding episodes. Overlapping runs of `cluster_feeds` feeds
+/// within `cluster_window` are joined into one episode.
+pub fn cluster_feeds(feeds: &[Feed], config: &FeedingConfig) -> Vec<ClusterFeeding> {
+    let n = config.cluster_feeds.max(2);
+    let mut clusters: Vec<(usize, usize)> = Vec::new();
+    for i in 0..feeds.len() {
+        let j = i + n - 1;
+        if j >= feeds.len() || feeds[j].start().signed_duration_since(feeds[i].start()) > config.cluster_window {
+            continue;
+        }
+        match clusters.last_mut() {
+            Some(last) if i <= last.1 => last.1 = j,
+            _ => clusters.push((i, j)),
+        }
+    }
+    clusters.into_iter().map(|(i, j)| {
+        ClusterFeeding{
+            start: feeds[i].start(),
+            end: feeds[j].start(),
+            feeds: j - i + 1,
+        }
+    }).collect()
+}
+
+/// Feeds on one date.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct FeedingDay {
+    pub date: NaiveDate,
+    /// The number of feeds, breast and bottle combined.
+    pub feeds: usize,
+    pub bottles: usize,
+    pub bottle_volume: Volume,
+    #[serde(serialize_with = "duration_serialize")]
+    pub left_breast: chrono::Duration,
+    #[serde(serialize_with = "duration_serialize")]
+    pub right_breast: chrono::Duration,
+    /// The mean interval between feeds, for intervals ending on this date.
+    #[serde(serialize_with = "option_duration_serialize")]
+    pub mean_interval: Option<chrono::Duration>,
+}
+
+/// Totals feeds by the date they started on.
+pub fn feeding_by_date(events: &[FeedingEvent], config: &FeedingConfig) -> Vec<FeedingDay> {
+    let feeds = feeds(events, config);
+    let intervals = group_by_date(feed_intervals(&feeds), |fi| fi.to.date_naive());
+    group_by_date(feeds, |f| f.start().date_naive()).into_iter().map(|(date, feeds)| {
+        let mut day = FeedingDay{
+            date,
+            feeds: feeds.len(),
+            bottles: 0,
+            bottle_volume: Volume::default(),
+            left_breast: chrono::Duration::zero(),
+            right_breast: chrono::Duration::zero(),
+            mean_interval: intervals.get(&date).map(|fis| {
+                fis.iter().fold(chrono::Duration::zero(), |a, fi| a + fi.interval) / fis.len() as i32
+            }),
+        };
+        for fe in feeds.iter().flat_map(|f| &f.events) {
+            match *fe {
+                FeedingEvent::Bottle(ref be) => {
+                    day.bottles += 1;
+                    day.bottle_volume = day.bottle_volume + be.volume;
+                },
+                FeedingEvent::LeftBreast(ref be) => day.left_breast += be.duration,
+                FeedingEvent::RightBreast(ref be) => day.right_breast += be.duration,
+            }
+        }
+        day
+    }).collect()
+}
@@ -0,0 +1,380 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use csv;
+
+use super::{Length,Mass,MeasureEvent,Timestamp};
+
+/// A child's sex, which selects the reference table.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Serialize,Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sex {
+    Male,
+    Female,
+}
+
+impl FromStr for Sex {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Sex, String> {
+        match s.to_lowercase().as_str() {
+            "male" | "m" | "boy" => Ok(Sex::Male),
+            "female" | "f" | "girl" => Ok(Sex::Female),
+            _ => Err(format!("unknown sex {}; expecting male or female", s)),
+        }
+    }
+}
+
+/// A measurement with a reference table.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Serialize)]
+pub enum GrowthMeasure {
+    Weight,
+    /// Length, or height once standing.
+    Height,
+    HeadCirc,
+}
+
+impl FromStr for GrowthMeasure {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<GrowthMeasure, String> {
+        match s {
+            "weight" => Ok(GrowthMeasure::Weight),
+            "height" | "length" => Ok(GrowthMeasure::Height),
+            "head" => Ok(GrowthMeasure::HeadCirc),
+            _ => Err(format!("unknown measure {}; expecting weight, height or head", s)),
+        }
+    }
+}
+
+impl fmt::Display for GrowthMeasure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GrowthMeasure::Weight => write!(f, "weight"),
+            GrowthMeasure::Height => write!(f, "height"),
+            GrowthMeasure::HeadCirc => write!(f, "head"),
+        }
+    }
+}
+
+/// The LMS parameters of a reference distribution at one age, with `M` in
+/// kilograms or centimetres.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Lms {
+    pub age_months: f64,
+    pub l: f64,
+    pub m: f64,
+    pub s: f64,
+}
+
+impl Lms {
+    pub fn z_score(&self, x: f64) -> f64 {
+        if self.l.abs() < 1e-9 {
+            (x / self.m).ln() / self.s
+        } else {
+            ((x / self.m).powf(self.l) - 1.0) / (self.l * self.s)
+        }
+    }
+}
+
+/// The percentile, from 0 to 100, of a z-score.
+pub fn percentile(z: f64) -> f64 {
+    // Abramowitz and Stegun 7.1.26, accurate to about 1e-7.
+    let x = z.abs() / 2f64.sqrt();
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-x * x).exp();
+    50.0 * (1.0 + if z < 0.0 { -erf } else { erf })
+}
+
+/// LMS tables by sex and measure, with ages in months.
+#[derive(Debug,Clone,PartialEq)]
+pub struct GrowthReference {
+    tables: BTreeMap<(Sex, GrowthMeasure), Vec<Lms>>,
+}
+
+impl GrowthReference {
+    /// The WHO Child Growth Standards from birth to 24 months. The CDC
+    /// tables for older children aren't built in; add them with
+    /// `with_cdc_reader` or `with_cdc_path`.
+    pub fn who() -> GrowthReference {
+        let tables: &[(Sex, GrowthMeasure, LmsTable)] = &[
+            (Sex::Male, GrowthMeasure::Weight, WHO_WEIGHT_BOYS),
+            (Sex::Female, GrowthMeasure::Weight, WHO_WEIGHT_GIRLS),
+            (Sex::Male, GrowthMeasure::Height, WHO_LENGTH_BOYS),
+            (Sex::Female, GrowthMeasure::Height, WHO_LENGTH_GIRLS),
+            (Sex::Male, GrowthMeasure::HeadCirc, WHO_HEAD_BOYS),
+            (Sex::Female, GrowthMeasure::HeadCirc, WHO_HEAD_GIRLS),
+        ];
+        let mut reference = GrowthReference{tables: BTreeMap::new()};
+        for &(sex, measure, rows) in tables {
+            reference.tables.insert((sex, measure), rows.iter().enumerate().map(|(month, &(l, m, s))| {
+                Lms{age_months: month as f64, l, m, s}
+            }).collect());
+        }
+        reference
+    }
+
+    /// Extends a table with rows from a CDC LMS file such as `wtage.csv`,
+    /// which has `Sex` (1 for male, 2 for female), `Agemos`, `L`, `M` and
+    /// `S` columns. Only ages beyond the end of the existing table are used,
+    /// so the WHO standards are kept up to 24 months. Rows that aren't
+    /// numeric, such as repeated headers, are skipped.
+    pub fn with_cdc_reader<R: io::Read>(mut self, rdr: R, measure: GrowthMeasure) -> Result<GrowthReference, csv::Error> {
+        let mut rdr = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(rdr);
+        let headers = rdr.headers()?.clone();
+        let column = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+        let columns = match (column("Sex"), column("Agemos"), column("L"), column("M"), column("S")) {
+            (Some(sex), Some(age), Some(l), Some(m), Some(s)) => [sex, age, l, m, s],
+            _ => return Ok(self),
+        };
+        for record in rdr.records() {
+            let record = record?;
+            let values: Vec<f64> = columns.iter().filter_map(|&i| record.get(i)?.parse().ok()).collect();
+            if values.len() != columns.len() {
+                continue;
+            }
+            let sex = match values[0] as u32 {
+                1 => Sex::Male,
+                2 => Sex::Female,
+                _ => continue,
+            };
+            let table = self.tables.entry((sex, measure)).or_default();
+            if table.last().is_none_or(|last| values[1] > last.age_months) {
+                table.push(Lms{age_months: values[1], l: values[2], m: values[3], s: values[4]});
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn with_cdc_path<P: AsRef<Path>>(self, path: P, measure: GrowthMeasure) -> Result<GrowthReference, csv::Error> {
+        let file = File::open(path).map_err(csv::Error::from)?;
+        self.with_cdc_reader(file, measure)
+    }
+
+    /// The oldest age in months with parameters for a sex and measure.
+    pub fn max_age_months(&self, sex: Sex, measure: GrowthMeasure) -> Option<f64> {
+        self.tables.get(&(sex, measure))?.last().map(|r| r.age_months)
+    }
+
+    /// The parameters at an age, interpolated between the table's ages.
+    /// `None` outside the table.
+    pub fn lms(&self, sex: Sex, measure: GrowthMeasure, age_months: f64) -> Option<Lms> {
+        let table = self.tables.get(&(sex, measure))?;
+        let i = table.iter().position(|r| r.age_months >= age_months)?;
+        let hi = table[i];
+        if i == 0 {
+            return if hi.age_months == age_months { Some(hi) } else { None };
+        }
+        let lo = table[i - 1];
+        let f = (age_months - lo.age_months) / (hi.age_months - lo.age_months);
+        let lerp = |a: f64, b: f64| a + (b - a) * f;
+        Some(Lms{age_months, l: lerp(lo.l, hi.l), m: lerp(lo.m, hi.m), s: lerp(lo.s, hi.s)})
+    }
+}
+
+/// What measurements are compared against: a reference and the child's
+/// birth date and sex.
+#[derive(Debug,Clone,PartialEq)]
+pub struct GrowthChart {
+    pub reference: GrowthReference,
+    pub birth: NaiveDate,
+    pub sex: Sex,
+}
+
+impl GrowthChart {
+    /// The age in months on a date, taking a month as 1/12 of a year.
+    pub fn age_months(&self, date: NaiveDate) -> f64 {
+        date.signed_duration_since(self.birth).num_days() as f64 / (365.25 / 12.0)
+    }
+
+    /// The z-score of a measurement, in kilograms or centimetres, on a date.
+    pub fn z_score(&self, measure: GrowthMeasure, date: NaiveDate, value: f64) -> Option<f64> {
+        self.reference.lms(self.sex, measure, self.age_months(date)).map(|lms| lms.z_score(value))
+    }
+}
+
+/// A measurement alongside the change in weight since the last one.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct GrowthRow {
+    pub time: Timestamp,
+    /// The age in months, when compared against a chart.
+    pub age_months: Option<f64>,
+    pub weight: Option<Mass>,
+    pub weight_z: Option<f64>,
+    pub weight_percentile: Option<f64>,
+    pub height: Option<Length>,
+    pub height_z: Option<f64>,
+    pub height_percentile: Option<f64>,
+    pub head_circ: Option<Length>,
+    pub head_circ_z: Option<f64>,
+    pub head_circ_percentile: Option<f64>,
+    /// The average daily weight gain since the previous weighing.
+    pub gain_per_day: Option<Mass>,
+}
+
+impl GrowthRow {
+    pub fn percentile(&self, measure: GrowthMeasure) -> Option<f64> {
+        match measure {
+            GrowthMeasure::Weight => self.weight_percentile,
+            GrowthMeasure::Height => self.height_percentile,
+            GrowthMeasure::HeadCirc => self.head_circ_percentile,
+        }
+    }
+}
+
+/// Lists measurements in time order with the weight gained between them,
+/// and z-scores and percentiles when a chart is given.
+pub fn growth_table(measurements: &[MeasureEvent], chart: Option<&GrowthChart>) -> Vec<GrowthRow> {
+    let mut measurements = measurements.to_vec();
+    measurements.sort_by_key(|me| me.time);
+    let mut last_weight: Option<(Timestamp, Mass)> = None;
+    measurements.into_iter().map(|me| {
+        let mut gain_per_day = None;
+        if let Some(weight) = me.weight {
+            if let Some((time, last)) = last_weight {
+                let days = me.time.signed_duration_since(time).num_minutes() as f32 / (24.0 * 60.0);
+                if days > 0.0 {
+                    gain_per_day = Some(Mass::from_kg((weight.kg() - last.kg()) / days));
+                }
+            }
+            last_weight = Some((me.time, weight));
+        }
+        let date = me.time.date_naive();
+        let z = |measure: GrowthMeasure, value: Option<f32>| -> Option<f64> {
+            chart?.z_score(measure, date, value? as f64)
+        };
+        let weight_z = z(GrowthMeasure::Weight, me.weight.map(|w| w.kg()));
+        let height_z = z(GrowthMeasure::Height, me.height.map(|h| h.cm()));
+        let head_circ_z = z(GrowthMeasure::HeadCirc, me.head_circ.map(|h| h.cm()));
+        GrowthRow{
+            time: me.time,
+            age_months: chart.map(|c| c.age_months(date)),
+            weight: me.weight,
+            weight_z,
+            weight_percentile: weight_z.map(percentile),
+            height: me.height,
+            height_z,
+            height_percentile: height_z.map(percentile),
+            head_circ: me.head_circ,
+            head_circ_z,
+            head_circ_percentile: head_circ_z.map(percentile),
+            gain_per_day,
+        }
+    }).collect()
+}
+
+/// The percentile lines drawn on growth charts.
+pub const MAJOR_PERCENTILES: [f64; 7] = [3.0, 10.0, 25.0, 50.0, 75.0, 90.0, 97.0];
+
+/// A change between successive measurements that crosses percentile lines.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct PercentileCrossing {
+    pub measure: GrowthMeasure,
+    pub from: Timestamp,
+    pub to: Timestamp,
+    pub from_percentile: f64,
+    pub to_percentile: f64,
+    /// The number of `MAJOR_PERCENTILES` lines crossed, negative when
+    /// crossing downwards.
+    pub lines: i32,
+}
+
+/// Finds successive measurements of each kind that cross at least one of
+/// `MAJOR_PERCENTILES`, in time order.
+pub fn percentile_crossings(rows: &[GrowthRow]) -> Vec<PercentileCrossing> {
+    let mut crossings = Vec::new();
+    for &measure in &[GrowthMeasure::Weight, GrowthMeasure::Height, GrowthMeasure::HeadCirc] {
+        let points: Vec<(Timestamp, f64)> = rows.iter().filter_map(|r| r.percentile(measure).map(|p| (r.time, p))).collect();
+        for w in points.windows(2) {
+            let ((from, p0), (to, p1)) = (w[0], w[1]);
+            let above = |p: f64| MAJOR_PERCENTILES.iter().filter(|&&line| p >= line).count() as i32;
+            let lines = above(p1) - above(p0);
+            if lines != 0 {
+                crossings.push(PercentileCrossing{measure, from, to, from_percentile: p0, to_percentile: p1, lines});
+            }
+        }
+    }
+    crossings.sort_by_key(|c| c.to);
+    crossings
+}
+
+// WHO Child Growth Standards, L, M and S by month from birth to 24 months.
+type LmsTable = &'static [(f64, f64, f64)];
+
+const WHO_WEIGHT_BOYS: LmsTable = &[
+    (0.3487, 3.3464, 0.14602), (0.2297, 4.4709, 0.13395), (0.1970, 5.5675, 0.12385),
+    (0.1738, 6.3762, 0.11727), (0.1553, 7.0023, 0.11316), (0.1395, 7.5105, 0.11080),
+    (0.1257, 7.9340, 0.10958), (0.1134, 8.2970, 0.10902), (0.1021, 8.6151, 0.10882),
+    (0.0917, 8.9014, 0.10881), (0.0820, 9.1649, 0.10891), (0.0730, 9.4122, 0.10906),
+    (0.0644, 9.6479, 0.10925), (0.0563, 9.8749, 0.10949), (0.0487, 10.0953, 0.10976),
+    (0.0413, 10.3108, 0.11007), (0.0343, 10.5228, 0.11041), (0.0275, 10.7319, 0.11079),
+    (0.0211, 10.9385, 0.11119), (0.0148, 11.1430, 0.11164), (0.0087, 11.3462, 0.11211),
+    (0.0029, 11.5486, 0.11261), (-0.0028, 11.7504, 0.11314), (-0.0083, 11.9514, 0.11369),
+    (-0.0137, 12.1515, 0.11426),
+];
+
+const WHO_WEIGHT_GIRLS: LmsTable = &[
+    (0.3809, 3.2322, 0.14171), (0.1714, 4.1873, 0.13724), (0.0962, 5.1282, 0.13000),
+    (0.0402, 5.8458, 0.12619), (-0.0050, 6.4237, 0.12402), (-0.0430, 6.8985, 0.12274),
+    (-0.0756, 7.2970, 0.12204), (-0.1039, 7.6422, 0.12178), (-0.1288, 7.9487, 0.12181),
+    (-0.1507, 8.2254, 0.12199), (-0.1700, 8.4800, 0.12223), (-0.1872, 8.7192, 0.12247),
+    (-0.2024, 8.9481, 0.12268), (-0.2158, 9.1699, 0.12283), (-0.2278, 9.3870, 0.12294),
+    (-0.2384, 9.6008, 0.12299), (-0.2478, 9.8124, 0.12303), (-0.2562, 10.0226, 0.12306),
+    (-0.2637, 10.2315, 0.12309), (-0.2703, 10.4393, 0.12315), (-0.2762, 10.6464, 0.12323),
+    (-0.2815, 10.8534, 0.12335), (-0.2862, 11.0608, 0.12350), (-0.2903, 11.2688, 0.12369),
+    (-0.2941, 11.4775, 0.12390),
+];
+
+const WHO_LENGTH_BOYS: LmsTable = &[
+    (1.0, 49.8842, 0.03795), (1.0, 54.7244, 0.03557), (1.0, 58.4249, 0.03424),
+    (1.0, 61.4292, 0.03328), (1.0, 63.8860, 0.03257), (1.0, 65.9026, 0.03204),
+    (1.0, 67.6236, 0.03165), (1.0, 69.1645, 0.03139), (1.0, 70.5994, 0.03124),
+    (1.0, 71.9687, 0.03117), (1.0, 73.2812, 0.03118), (1.0, 74.5388, 0.03125),
+    (1.0, 75.7488, 0.03137), (1.0, 76.9186, 0.03154), (1.0, 78.0497, 0.03174),
+    (1.0, 79.1458, 0.03197), (1.0, 80.2113, 0.03222), (1.0, 81.2487, 0.03250),
+    (1.0, 82.2587, 0.03279), (1.0, 83.2418, 0.03310), (1.0, 84.1996, 0.03342),
+    (1.0, 85.1348, 0.03376), (1.0, 86.0477, 0.03410), (1.0, 86.9410, 0.03445),
+    (1.0, 87.8161, 0.03479),
+];
+
+const WHO_LENGTH_GIRLS: LmsTable = &[
+    (1.0, 49.1477, 0.03790), (1.0, 53.6872, 0.03640), (1.0, 57.0673, 0.03568),
+    (1.0, 59.8029, 0.03520), (1.0, 62.0899, 0.03486), (1.0, 64.0301, 0.03463),
+    (1.0, 65.7311, 0.03448), (1.0, 67.2873, 0.03441), (1.0, 68.7498, 0.03440),
+    (1.0, 70.1435, 0.03444), (1.0, 71.4818, 0.03452), (1.0, 72.7710, 0.03464),
+    (1.0, 74.0150, 0.03479), (1.0, 75.2176, 0.03496), (1.0, 76.3817, 0.03514),
+    (1.0, 77.5099, 0.03534), (1.0, 78.6055, 0.03555), (1.0, 79.6710, 0.03576),
+    (1.0, 80.7079, 0.03598), (1.0, 81.7182, 0.03620), (1.0, 82.7036, 0.03643),
+    (1.0, 83.6654, 0.03666), (1.0, 84.6040, 0.03688), (1.0, 85.5202, 0.03711),
+    (1.0, 86.4153, 0.03734),
+];
+
+const WHO_HEAD_BOYS: LmsTable = &[
+    (1.0, 34.4618, 0.03686), (1.0, 37.2759, 0.03133), (1.0, 39.1285, 0.02997),
+    (1.0, 40.5135, 0.02918), (1.0, 41.6317, 0.02868), (1.0, 42.5576, 0.02837),
+    (1.0, 43.3306, 0.02817), (1.0, 43.9803, 0.02804), (1.0, 44.5300, 0.02796),
+    (1.0, 44.9998, 0.02792), (1.0, 45.4051, 0.02790), (1.0, 45.7573, 0.02789),
+    (1.0, 46.0661, 0.02789), (1.0, 46.3395, 0.02789), (1.0, 46.5844, 0.02791),
+    (1.0, 46.8060, 0.02792), (1.0, 47.0088, 0.02795), (1.0, 47.1962, 0.02797),
+    (1.0, 47.3711, 0.02800), (1.0, 47.5357, 0.02803), (1.0, 47.6919, 0.02806),
+    (1.0, 47.8408, 0.02810), (1.0, 47.9833, 0.02813), (1.0, 48.1201, 0.02817),
+    (1.0, 48.2515, 0.02821),
+];
+
+const WHO_HEAD_GIRLS: LmsTable = &[
+    (1.0, 33.8787, 0.03496), (1.0, 36.5463, 0.03210), (1.0, 38.2521, 0.03168),
+    (1.0, 39.5328, 0.03140), (1.0, 40.5817, 0.03119), (1.0, 41.4590, 0.03102),
+    (1.0, 42.1995, 0.03087), (1.0, 42.8290, 0.03075), (1.0, 43.3671, 0.03063),
+    (1.0, 43.8300, 0.03053), (1.0, 44.2319, 0.03044), (1.0, 44.5844, 0.03035),
+    (1.0, 44.8965, 0.03027), (1.0, 45.1752, 0.03019), (1.0, 45.4265, 0.03012),
+    (1.0, 45.6551, 0.03006), (1.0, 45.8650, 0.02999), (1.0, 46.0598, 0.02993),
+    (1.0, 46.2424, 0.02987), (1.0, 46.4152, 0.02982), (1.0, 46.5801, 0.02977),
+    (1.0, 46.7384, 0.02972), (1.0, 46.8913, 0.02967), (1.0, 47.0391, 0.02962),
+    (1.0, 47.1822, 0.02957),
+];
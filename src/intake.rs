@@ -0,0 +1,54 @@
+use chrono::NaiveDate;
+
+use super::{group_by_date,FeedingEvent,Milk,Volume};
+
+/// Bottle intake by type of milk on one date.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct IntakeDay {
+    pub date: NaiveDate,
+    pub bottles: usize,
+    pub breast_milk: Volume,
+    pub formula: Volume,
+    pub mixed: Volume,
+    pub donor: Volume,
+    /// Bottles with any other label.
+    pub other: Volume,
+    /// Bottles without a label.
+    pub unknown: Volume,
+    pub total: Volume,
+    /// The share of breast milk, own and donor, in bottles of breast milk
+    /// and formula, from 0 to 1. Mixed bottles count as half of each.
+    /// `None` when there were no such bottles.
+    pub breast_milk_share: Option<f64>,
+}
+
+/// Totals bottles by the type of milk and the date they were given on.
+pub fn intake_by_date(events: &[FeedingEvent]) -> Vec<IntakeDay> {
+    let bottles = events.iter().filter_map(|fe| match *fe {
+        FeedingEvent::Bottle(ref be) => Some(be),
+        _ => None,
+    });
+    group_by_date(bottles, |be| be.time.date_naive()).into_iter().map(|(date, bottles)| {
+        let of = |f: &dyn Fn(&Milk) -> bool| -> Volume {
+            bottles.iter().filter(|be| f(&be.milk)).map(|be| be.volume).sum()
+        };
+        let breast_milk = of(&|m| *m == Milk::BreastMilk);
+        let formula = of(&|m| *m == Milk::Formula);
+        let mixed = of(&|m| *m == Milk::Mixed);
+        let donor = of(&|m| *m == Milk::Donor);
+        let breast = (breast_milk + donor).ml() as f64 + mixed.ml() as f64 / 2.0;
+        let known = (breast_milk + donor + mixed + formula).ml() as f64;
+        IntakeDay{
+            date,
+            bottles: bottles.len(),
+            breast_milk,
+            formula,
+            mixed,
+            donor,
+            other: of(&|m| matches!(*m, Milk::Other(_))),
+            unknown: of(&|m| *m == Milk::Unknown),
+            total: bottles.iter().map(|be| be.volume).sum(),
+            breast_milk_share: if known > 0.0 { Some(breast / known) } else { None },
+        }
+    }).collect()
+}
@@ -4,22 +4,78 @@ extern crate serde_derive;
 #[macro_use]
 extern crate lazy_static;
 extern crate serde;
+extern crate serde_json;
 extern crate chrono;
+extern crate chrono_tz;
 extern crate regex;
-
-use chrono::TimeZone;
-use std::error::Error;
+extern crate toml;
+
+mod breast;
+mod children;
+mod config;
+mod daily;
+mod diapers;
+mod error;
+mod feeding;
+mod growth;
+mod intake;
+mod merge;
+mod metric;
+mod period;
+mod profile;
+mod pumping;
+mod rolling;
+mod sleep;
+mod stool;
+mod summary;
+mod tz;
+mod units;
+mod vaccination;
+mod wakeups;
+mod writer;
+
+use std::fmt;
 use std::io;
 use std::fs::File;
 use std::path::Path;
-use chrono::offset::{Local};
 use regex::Regex;
 
-const ML_PER_OZ: f32 = 29.574;
+pub use breast::{last_side,next_side,side_balance_by_date,side_balance_by_period,NextSide,NextSideConfig,Side,
+                 SideBalance};
+pub use children::{children,compare_children,filter_child,group_by_child,ChildComparison,ChildEvent,ComparisonRow};
+pub use config::{Clock,DateOrder,DecimalSeparator,ImportConfig,UnitSystem};
+pub use daily::{group_by_date,DateRange};
+pub use diapers::{diaper_alerts,diapers_by_date,DiaperAlert,DiaperAlertKind,DiaperConfig,DiaperDay};
+pub use error::{Error,Field};
+pub use feeding::{cluster_feeds,feed_intervals,feeding_by_date,feeds,intervals_by_time_of_day,ClusterFeeding,Feed,
+                  FeedInterval,FeedingConfig,FeedingDay,TimeOfDayIntervals};
+pub use growth::{growth_table,percentile,percentile_crossings,GrowthChart,GrowthMeasure,GrowthReference,GrowthRow,Lms,
+                 PercentileCrossing,Sex,MAJOR_PERCENTILES};
+pub use intake::{intake_by_date,IntakeDay};
+pub use merge::{merge,MergeReport,NearDuplicate};
+pub use metric::Metric;
+pub use period::{aggregate,sparkline,Period,PeriodStats};
+pub use profile::{profiles_from_json,profiles_from_path,profiles_from_toml,BabyProfile,ProfileError,TERM_WEEKS};
+pub use pumping::{pumping_by_date,pumping_mismatches,yield_by_time_of_day,PumpingDay,PumpingMismatch,TimeOfDayYield};
+pub use rolling::{rolling,Alignment,MissingDays,RollingConfig,RollingPoint};
+pub use sleep::{sleep_by_date,sleep_sessions,SleepDay,SleepSession,DEFAULT_SESSION_GAP_MINUTES};
+pub use stool::{diaper_log,stool_summary,DiaperDetail,DiaperLogRow,DiaperQuery,StoolAmount,StoolColor,StoolConsistency,
+                StoolSummary};
+pub use summary::{daily_summaries,DailySummary,SummaryConfig,SummaryTable};
+pub use tz::{DstPolicy,TimeZoneSpec,Timestamp};
+pub use units::{Length,Mass,Volume};
+pub use vaccination::{vaccination_history,DoseRow,DoseStatus,Schedule,ScheduledDose,VaccinationHistory};
+pub use wakeups::{nights,Night,NightConfig,TimeWindow};
+pub use writer::BabyManagerWriter;
+
 
 #[derive(Debug,Deserialize)]
 #[serde(rename_all = "PascalCase")]
 struct RawEvent<'a> {
+    #[serde(skip)]
+    line: u64,
+    #[serde(skip)]
+    config: ImportConfig,
     #[serde(rename = "Type")]
 	typ: &'a str,
 	start: &'a str,
@@ -28,10 +84,13 @@ struct RawEvent<'a> {
 	extra: &'a str,
 	extra2: &'a str,
 	note: &'a str,
+    /// Names the child in exports covering several.
+    #[serde(default)]
+	child: &'a str,
 }
 
 impl<'a> RawEvent<'a> {
-    fn into_event(self) -> Result<Event, Box<Error>> {
+    fn into_event(self) -> Result<Event, Error> {
         match self.typ {
             "Sleep" => Ok(Event::Sleep(self.to_sleep_event()?)),
             "Diaper" => Ok(Event::Diaper(self.to_diaper_event()?)),
@@ -39,172 +98,207 @@ impl<'a> RawEvent<'a> {
             "Left breast" => Ok(Event::Feeding(FeedingEvent::LeftBreast(self.to_breast_event()?))),
             "Right breast" => Ok(Event::Feeding(FeedingEvent::RightBreast(self.to_breast_event()?))),
             "Pumping" => Ok(Event::Pumping(self.to_pumping_event()?)),
-            "Vaccination" => Ok(Event::TummyTime(self.to_tummy_time_event()?)),
+            "Tummy time" => Ok(Event::TummyTime(self.to_tummy_time_event()?)),
+            "Vaccination" => Ok(Event::Vaccination(self.to_vaccination_event()?)),
             "Meal" => Ok(Event::Meal(self.to_meal_event()?)),
             "Measure" => Ok(Event::Measure(self.to_measure_event()?)),
             "Note" => Ok(Event::Note(self.to_note_event()?)),
-            _ => Err(From::from(format!("unknown type: {}", self.typ))),
+            _ => Err(Error::UnknownType(self.field("Type", self.typ))),
+        }
+    }
+
+    fn field(&self, column: &'static str, text: &str) -> Field {
+        Field{
+            line: self.line,
+            column,
+            text: text.to_string(),
         }
     }
 
-    fn to_sleep_event(&self) -> Result<SleepEvent, Box<Error>> {
+    fn to_sleep_event(&self) -> Result<SleepEvent, Error> {
         Ok(SleepEvent{
-            start: datetime_from_str(&self.start)?,
-            end:
-                if self.end.len() == 0 {
-                    None
-                } else {
-                    Some(datetime_from_str(&self.end)?)
-                },
+            start: self.start()?,
+            end: self.end()?,
             duration: self.to_duration()?,
             note: self.note.to_string(),
         })
     }
 
-    fn to_diaper_event(&self) -> Result<DiaperEvent, Box<Error>> {
+    fn to_diaper_event(&self) -> Result<DiaperEvent, Error> {
         Ok(DiaperEvent{
-            time: datetime_from_str(&self.start)?,
+            time: self.start()?,
             pee: self.extra.contains("Urine"),
             poo: self.extra.contains("Feces"),
+            detail: DiaperDetail::from_texts(&[self.extra, self.extra2, self.note]),
             note: self.note.to_string(),
         })
     }
 
-    fn to_bottle_event(&self) -> Result<BottleEvent, Box<Error>> {
+    fn to_bottle_event(&self) -> Result<BottleEvent, Error> {
         Ok(BottleEvent{
-            time: datetime_from_str(&self.start)?,
-            milk: match self.extra2 {
-                "Mom's milk" => Milk::BreastMilk,
-                "Formula milk" => Milk::Formula,
-                _ => Milk::Unknown,
-            },
-            ounces: {
-                if self.extra.ends_with(" oz") {
-                    self.extra[..self.extra.len()-3].parse::<f32>()? 
-                } else {
-                    0.0
-                }
-            },
+            time: self.start()?,
+            milk: Milk::from_label(self.extra2),
+            volume: self.to_volume()?,
             note: self.note.to_string(),
         })
     }
 
-    fn to_breast_event(&self) -> Result<BreastEvent, Box<Error>> {
+    fn to_breast_event(&self) -> Result<BreastEvent, Error> {
         Ok(BreastEvent{
-            start: datetime_from_str(&self.start)?,
-            end:
-                if self.end.len() == 0 {
-                    None
-                } else {
-                    Some(datetime_from_str(&self.end)?)
-                },
+            start: self.start()?,
+            end: self.end()?,
             duration: self.to_duration()?,
             note: self.note.to_string(),
         })
     }
 
-    fn to_pumping_event(&self) -> Result<PumpingEvent, Box<Error>> {
+    fn to_pumping_event(&self) -> Result<PumpingEvent, Error> {
         lazy_static! {
             static ref L_RE: Regex = Regex::new(r"(\d+)\s*L").unwrap();
             static ref R_RE: Regex = Regex::new(r"(\d+)\s*R").unwrap();
         }
+        // The per-side split is noted in millilitres, e.g. "60L 58R".
         let left = L_RE.
             captures(self.note).
             and_then(|x| {
-                x.get(1).unwrap().as_str().parse::<i32>().ok()
-            });
+                x.get(1).unwrap().as_str().parse::<f32>().ok()
+            }).
+            map(Volume::from_ml);
         let right = R_RE.
             captures(self.note).
             and_then(|x| {
-                x.get(1).unwrap().as_str().parse::<i32>().ok()
-            });
-        
+                x.get(1).unwrap().as_str().parse::<f32>().ok()
+            }).
+            map(Volume::from_ml);
+
         Ok(PumpingEvent{
-            start: datetime_from_str(&self.start)?,
-            ml: {
-                if self.extra.ends_with(" oz") {
-                    (self.extra[..self.extra.len()-3].parse::<f32>()? * ML_PER_OZ) as i32
-                } else {
-                    0
-                }
-            },
-            left_ml: left,
-            right_ml: right,
+            start: self.start()?,
+            volume: self.to_volume()?,
+            left,
+            right,
             note: self.note.to_string(),
         })
     }
 
-    fn to_tummy_time_event(&self) -> Result<TummyTimeEvent, Box<Error>> {
+    fn to_tummy_time_event(&self) -> Result<TummyTimeEvent, Error> {
         Ok(TummyTimeEvent{
-            start: datetime_from_str(&self.start)?,
-            end:
-                if self.end.len() == 0 {
-                    None
-                } else {
-                    Some(datetime_from_str(&self.end)?)
-                },
+            start: self.start()?,
+            end: self.end()?,
             duration: self.to_duration()?,
             note: self.note.to_string(),
         })
     }
 
-    fn to_meal_event(&self) -> Result<MealEvent, Box<Error>> {
+    // The vaccine is named in Extra. The dose number may be given in any of
+    // the extra columns or the note, e.g. "DTaP, dose 2" or "Hib #3".
+    fn to_vaccination_event(&self) -> Result<VaccinationEvent, Error> {
+        lazy_static! {
+            static ref DOSE_RE: Regex = Regex::new(
+                r"(?i)[,;(]?\s*(?:dose\s*#?\s*(\d+)|#\s*(\d+)|(\d+)(?:st|nd|rd|th)\s+dose)\)?").unwrap();
+        }
+        let dose = [self.extra, self.extra2, self.note].iter().
+            filter_map(|s| DOSE_RE.captures(s)).
+            next().
+            and_then(|x| {
+                x.iter().skip(1).flatten().next().unwrap().as_str().parse::<u32>().ok()
+            });
+        Ok(VaccinationEvent{
+            time: self.start()?,
+            vaccine: DOSE_RE.replace_all(self.extra, "").trim().to_string(),
+            dose,
+            note: self.note.to_string(),
+        })
+    }
+
+    fn to_meal_event(&self) -> Result<MealEvent, Error> {
         Ok(MealEvent{
-            time: datetime_from_str(&self.start)?,
+            time: self.start()?,
             note: self.note.to_string(),
         })
     }
 
-    fn to_measure_event(&self) -> Result<MeasureEvent, Box<Error>> {
+    fn to_measure_event(&self) -> Result<MeasureEvent, Error> {
         lazy_static! {
-            static ref WEIGHT_RE: Regex = Regex::new(r"Weight: (\d+(?:\.\d+)?) lb").unwrap();
-            static ref HEIGHT_RE: Regex = Regex::new(r"Height: (\d+(?:\.\d+)?) in").unwrap();
-            static ref HEAD_CIRC_RE: Regex = Regex::new(r"Head circumference: (\d+(?:\.\d+)?) in").unwrap();
+            static ref WEIGHT_RE: Regex = Regex::new(r"Weight: ([^,;]*)").unwrap();
+            static ref HEIGHT_RE: Regex = Regex::new(r"Height: ([^,;]*)").unwrap();
+            static ref HEAD_CIRC_RE: Regex = Regex::new(r"Head circumference: ([^,;]*)").unwrap();
         }
-        let weight = WEIGHT_RE.
-            captures(self.extra).
-            and_then(|x| {
-                x.get(1).unwrap().as_str().parse::<f32>().ok()
-            });
-        let height = HEIGHT_RE.
-            captures(self.extra).
-            and_then(|x| {
-                x.get(1).unwrap().as_str().parse::<f32>().ok()
-            });
-        let head_circ = HEAD_CIRC_RE.
-            captures(self.extra).
-            and_then(|x| {
-                x.get(1).unwrap().as_str().parse::<f32>().ok()
-            });
+        let units = self.config.units;
         Ok(MeasureEvent{
-            time: datetime_from_str(&self.start)?,
-            weight: weight,
-            height: height,
-            head_circ: head_circ,
+            time: self.start()?,
+            weight: self.to_measurement(&WEIGHT_RE, |s| Mass::parse(s, units))?,
+            height: self.to_measurement(&HEIGHT_RE, |s| Length::parse(s, units))?,
+            head_circ: self.to_measurement(&HEAD_CIRC_RE, |s| Length::parse(s, units))?,
             note: self.note.to_string(),
         })
     }
 
-    fn to_note_event(&self) -> Result<NoteEvent, Box<Error>> {
+    fn to_note_event(&self) -> Result<NoteEvent, Error> {
         Ok(NoteEvent{
-            time: datetime_from_str(&self.start)?,
+            time: self.start()?,
             note: self.note.to_string(),
         })
     }
 
-    fn to_duration(&self) -> Result<chrono::Duration, Box<Error>> {
-        let v: Vec<&str> = self.duration.split(':').collect();
-        if v.len() != 2 {
-            return Err(From::from(format!("Unable to parse duration {}; Expecting HH:MM format", self.duration)))
+    fn start(&self) -> Result<Timestamp, Error> {
+        self.datetime_from_str(self.start).
+            ok_or_else(|| Error::BadTimestamp(self.field("Start", self.start)))
+    }
+
+    fn end(&self) -> Result<Option<Timestamp>, Error> {
+        if self.end.is_empty() {
+            return Ok(None);
+        }
+        self.datetime_from_str(self.end).
+            map(Some).
+            ok_or_else(|| Error::BadTimestamp(self.field("End", self.end)))
+    }
+
+    fn datetime_from_str(&self, s: &str) -> Option<Timestamp> {
+        // Dates may be separated by '-' or '.' depending on the locale.
+        let s = s.replace(['-', '.'], "/");
+        chrono::NaiveDateTime::parse_from_str(&s, self.config.timestamp_format()).ok().
+            and_then(|dt| self.config.timezone.resolve(&dt, self.config.dst_policy))
+    }
+
+    fn to_duration(&self) -> Result<chrono::Duration, Error> {
+        duration_from_str(self.duration).
+            ok_or_else(|| Error::BadDuration(self.field("Duration", self.duration)))
+    }
+
+    // Volumes may be in "oz" or "ml". A blank field is no volume, as older
+    // exports leave it blank.
+    fn to_volume(&self) -> Result<Volume, Error> {
+        if self.extra.trim().is_empty() {
+            return Ok(Volume::default());
+        }
+        let extra = self.config.normalize_decimals(self.extra);
+        Volume::parse(&extra, self.config.units).
+            ok_or_else(|| Error::BadQuantity(self.field("Extra", self.extra)))
+    }
+
+    // Returns None when the label is absent, and an error when the label is
+    // present but its value can't be parsed.
+    fn to_measurement<T, F>(&self, re: &Regex, parse: F) -> Result<Option<T>, Error>
+        where F: Fn(&str) -> Option<T> {
+        let extra = self.config.normalize_decimals(self.extra);
+        match re.captures(&extra) {
+            None => Ok(None),
+            Some(x) => parse(x.get(1).unwrap().as_str()).
+                map(Some).
+                ok_or_else(|| Error::BadMeasurement(self.field("Extra", self.extra))),
         }
-        let hours : i64 = v[0].parse()?;
-        let minutes : i64 = v[1].parse()?;
-        Ok(chrono::Duration::minutes(hours * 60 + minutes))
     }
 }
 
-fn datetime_from_str<T: AsRef<str>>(s: T) -> Result<chrono::DateTime<Local>, Box<Error>> {
-    Ok(Local.datetime_from_str(s.as_ref(), "%0d/%0m/%Y %H:%M")?)
+fn duration_from_str(s: &str) -> Option<chrono::Duration> {
+    let v: Vec<&str> = s.split(':').collect();
+    if v.len() != 2 {
+        return None;
+    }
+    let hours : i64 = v[0].parse().ok()?;
+    let minutes : i64 = v[1].parse().ok()?;
+    Some(chrono::Duration::minutes(hours * 60 + minutes))
 }
 
 fn str_from_duration(d: &chrono::Duration) ->String {
@@ -218,51 +312,102 @@ fn duration_serialize<S>(d: &chrono::Duration, serializer: S) -> Result<S::Ok, S
     serializer.serialize_str(str_from_duration(d).as_str())
 }
 
-#[derive(Debug,Clone,Serialize)]
+fn option_duration_serialize<S>(d: &Option<chrono::Duration>, serializer: S) -> Result<S::Ok, S::Error>
+    where S: serde::Serializer {
+    match *d {
+        Some(ref d) => duration_serialize(d, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+// An item of a list written by `list_serialize`.
+trait ListItem {
+    fn to_item(self) -> String;
+}
+
+impl ListItem for &String {
+    fn to_item(self) -> String {
+        self.clone()
+    }
+}
+
+impl ListItem for &chrono::Duration {
+    fn to_item(self) -> String {
+        str_from_duration(self)
+    }
+}
+
+// A count of something, e.g. `green 2`.
+impl<K: fmt::Display> ListItem for (&K, &usize) {
+    fn to_item(self) -> String {
+        format!("{} {}", self.0, self.1)
+    }
+}
+
+// Writes a list as one `; ` separated field, so that what holds it can be
+// written as a CSV row.
+fn list_serialize<'a, T, S>(items: &'a T, serializer: S) -> Result<S::Ok, S::Error>
+    where T: ?Sized, &'a T: IntoIterator, <&'a T as IntoIterator>::Item: ListItem, S: serde::Serializer {
+    let items: Vec<String> = items.into_iter().map(ListItem::to_item).collect();
+    serializer.serialize_str(&items.join("; "))
+}
+
+fn duration_deserialize<'de, D>(deserializer: D) -> Result<chrono::Duration, D::Error>
+    where D: serde::Deserializer<'de> {
+    use serde::de::Error;
+    let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+    duration_from_str(&s).
+        ok_or_else(|| D::Error::custom(format!("unable to parse duration {}; expecting HH:MM format", s)))
+}
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 pub enum Event {
     Sleep(SleepEvent),
     Diaper(DiaperEvent),
     Feeding(FeedingEvent),
     Pumping(PumpingEvent),
     TummyTime(TummyTimeEvent),
+    Vaccination(VaccinationEvent),
     Meal(MealEvent),
     Measure(MeasureEvent),
     Note(NoteEvent),
 }
 
 impl Event {
-    pub fn time(&self) -> chrono::DateTime<Local> {
-        match self {
-            &Event::Sleep(ref r) => r.start,
-            &Event::Diaper(ref r) => r.time,
-            &Event::Feeding(ref r) => r.time(),
-            &Event::Pumping(ref r) => r.start,
-            &Event::TummyTime(ref r) => r.start,
-            &Event::Meal(ref r) => r.time,
-            &Event::Measure(ref r) => r.time,
-            &Event::Note(ref r) => r.time,
+    pub fn time(&self) -> Timestamp {
+        match *self {
+            Event::Sleep(ref r) => r.start,
+            Event::Diaper(ref r) => r.time,
+            Event::Feeding(ref r) => r.time(),
+            Event::Pumping(ref r) => r.start,
+            Event::TummyTime(ref r) => r.start,
+            Event::Vaccination(ref r) => r.time,
+            Event::Meal(ref r) => r.time,
+            Event::Measure(ref r) => r.time,
+            Event::Note(ref r) => r.time,
         }
     }
 }
 
-#[derive(Debug,Clone,Serialize)]
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 pub struct SleepEvent {
-    pub start: chrono::DateTime<Local>,
-    pub end: Option<chrono::DateTime<Local>>,
-    #[serde(serialize_with = "duration_serialize")]
+    pub start: Timestamp,
+    pub end: Option<Timestamp>,
+    #[serde(serialize_with = "duration_serialize", deserialize_with = "duration_deserialize")]
     pub duration: chrono::Duration,
     pub note: String,
 }
 
-#[derive(Debug,Clone,Serialize)]
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 pub struct DiaperEvent {
-    pub time: chrono::DateTime<Local>,
+    pub time: Timestamp,
     pub pee: bool,
     pub poo: bool,
+    pub detail: DiaperDetail,
     pub note: String,
 }
 
-#[derive(Debug,Clone,Serialize)]
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 pub enum FeedingEvent {
     Bottle(BottleEvent),
     LeftBreast(BreastEvent),
@@ -270,153 +415,353 @@ pub enum FeedingEvent {
 }
 
 impl FeedingEvent {
-    pub fn time(&self) -> chrono::DateTime<Local> {
-        match self {
-            &FeedingEvent::Bottle(ref r) => r.time,
-            &FeedingEvent::LeftBreast(ref r) => r.start,
-            &FeedingEvent::RightBreast(ref r) => r.start,
+    pub fn time(&self) -> Timestamp {
+        match *self {
+            FeedingEvent::Bottle(ref r) => r.time,
+            FeedingEvent::LeftBreast(ref r) => r.start,
+            FeedingEvent::RightBreast(ref r) => r.start,
         }
     }
 }
 
-#[derive(Debug,Clone,Serialize)]
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 pub enum Milk {
     BreastMilk,
     Formula,
+    /// Breast milk and formula mixed in one bottle.
+    Mixed,
+    /// Breast milk from a donor.
+    Donor,
+    /// Any other label, e.g. `Cow's milk`.
+    Other(String),
     Unknown,
 }
 
-#[derive(Debug,Clone,Serialize)]
+impl Milk {
+    // Reads the milk type from the Extra2 column.
+    fn from_label(label: &str) -> Milk {
+        match label.trim().to_lowercase().as_str() {
+            "" => Milk::Unknown,
+            "mom's milk" | "breast milk" => Milk::BreastMilk,
+            "formula milk" | "formula" => Milk::Formula,
+            "mixed milk" | "mixed" => Milk::Mixed,
+            "donor milk" | "donor" => Milk::Donor,
+            _ => Milk::Other(label.trim().to_string()),
+        }
+    }
+}
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 pub struct BottleEvent {
-    pub time: chrono::DateTime<Local>,
+    pub time: Timestamp,
     pub milk: Milk,
-    pub ounces: f32,
+    pub volume: Volume,
     pub note: String,
 }
 
-#[derive(Debug,Clone,Serialize)]
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 pub struct BreastEvent {
-    pub start: chrono::DateTime<Local>,
-    pub end: Option<chrono::DateTime<Local>>,
-    #[serde(serialize_with = "duration_serialize")]
+    pub start: Timestamp,
+    pub end: Option<Timestamp>,
+    #[serde(serialize_with = "duration_serialize", deserialize_with = "duration_deserialize")]
     pub duration: chrono::Duration,
     pub note: String,
 }
 
-#[derive(Debug,Clone,Serialize)]
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 pub struct PumpingEvent {
-    pub start: chrono::DateTime<Local>,
-    pub ml: i32,
-    pub left_ml: Option<i32>,
-    pub right_ml: Option<i32>,
+    pub start: Timestamp,
+    pub volume: Volume,
+    pub left: Option<Volume>,
+    pub right: Option<Volume>,
     pub note: String,
 }
 
-impl PumpingEvent {
-    pub fn oz(&self) -> f32 {
-        self.ml as f32 / ML_PER_OZ
-    }
-}
-
-#[derive(Debug,Clone,Serialize)]
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 pub struct TummyTimeEvent {
-    pub start: chrono::DateTime<Local>,
-    pub end: Option<chrono::DateTime<Local>>,
-    #[serde(serialize_with = "duration_serialize")]
+    pub start: Timestamp,
+    pub end: Option<Timestamp>,
+    #[serde(serialize_with = "duration_serialize", deserialize_with = "duration_deserialize")]
     pub duration: chrono::Duration,
     pub note: String,
 }
 
-#[derive(Debug,Clone,Serialize)]
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct VaccinationEvent {
+    pub time: Timestamp,
+    pub vaccine: String,
+    pub dose: Option<u32>,
+    pub note: String,
+}
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 pub struct MealEvent {
-    pub time: chrono::DateTime<Local>,
+    pub time: Timestamp,
     pub note: String,
 }
 
-#[derive(Debug,Clone,Serialize)]
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 pub struct MeasureEvent {
-    pub time: chrono::DateTime<Local>,
-    pub weight: Option<f32>,
-    pub height: Option<f32>,
-    pub head_circ: Option<f32>,
+    pub time: Timestamp,
+    pub weight: Option<Mass>,
+    pub height: Option<Length>,
+    pub head_circ: Option<Length>,
     pub note: String,
 }
 
-#[derive(Debug,Clone,Serialize)]
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 pub struct NoteEvent {
-    pub time: chrono::DateTime<Local>,
+    pub time: Timestamp,
     pub note: String,
 }
 
+/// How `BabyManagerData` reacts to rows it can't parse.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum ParseMode {
+    /// Yield an `Err` for every row that fails to parse.
+    Strict,
+    /// Skip rows that fail to parse and record them in the `Diagnostics`.
+    /// I/O errors and unreadable headers are still returned as errors.
+    Lenient,
+}
+
+/// A row that was skipped while parsing in `ParseMode::Lenient`.
+#[derive(Debug)]
+pub struct SkippedRow {
+    pub line: u64,
+    pub record: Vec<String>,
+    pub error: Error,
+}
+
+/// A report of the rows read and skipped by a lenient parse.
+#[derive(Debug,Default)]
+pub struct Diagnostics {
+    pub rows_read: u64,
+    pub skipped: Vec<SkippedRow>,
+}
+
+impl Diagnostics {
+    /// The fraction of rows read that were skipped, between 0 and 1.
+    pub fn skipped_ratio(&self) -> f64 {
+        if self.rows_read == 0 {
+            0.0
+        } else {
+            self.skipped.len() as f64 / self.rows_read as f64
+        }
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "read {} rows, skipped {} ({:.1}%)",
+                 self.rows_read, self.skipped.len(), self.skipped_ratio() * 100.0)?;
+        for row in &self.skipped {
+            writeln!(f, "  {}: {}", row.error, row.record.join(","))?;
+        }
+        Ok(())
+    }
+}
+
 pub struct BabyManagerData<R> {
-    rdr: csv::Reader<R>
+    rdr: csv::Reader<R>,
+    mode: ParseMode,
+    config: ImportConfig,
+    child: Option<String>,
+    diagnostics: Diagnostics,
 }
 
 impl BabyManagerData<File> {
-    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<BabyManagerData<File>, Box<Error>> {
-        Ok(BabyManagerData{
-            rdr: csv::Reader::from_path(path)?,
-        })
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<BabyManagerData<File>, Error> {
+        Ok(BabyManagerData::new(csv::Reader::from_path(path)?))
     }
 }
 
 impl<R: io::Read> BabyManagerData<R> {
     pub fn from_reader(rdr: R) -> BabyManagerData<R> {
+        BabyManagerData::new(csv::Reader::from_reader(rdr))
+    }
+
+    fn new(rdr: csv::Reader<R>) -> BabyManagerData<R> {
         BabyManagerData{
-            rdr: csv::Reader::from_reader(rdr),
+            rdr,
+            mode: ParseMode::Strict,
+            config: ImportConfig::default(),
+            child: None,
+            diagnostics: Diagnostics::default(),
         }
     }
+
+    /// Sets how rows that fail to parse are handled. Defaults to
+    /// `ParseMode::Strict`.
+    pub fn with_mode(mut self, mode: ParseMode) -> BabyManagerData<R> {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the locale specific formats of the export. Defaults to
+    /// `ImportConfig::default()`.
+    pub fn with_config(mut self, config: ImportConfig) -> BabyManagerData<R> {
+        self.config = config;
+        self
+    }
+
+    /// Sets the timezone timestamps are interpreted in. Defaults to
+    /// `TimeZoneSpec::Local`.
+    pub fn with_timezone(mut self, tz: TimeZoneSpec) -> BabyManagerData<R> {
+        self.config.timezone = tz;
+        self
+    }
+
+    /// Sets how timestamps made ambiguous or nonexistent by daylight saving
+    /// transitions are resolved. Defaults to `DstPolicy::Earliest`.
+    pub fn with_dst_policy(mut self, dst: DstPolicy) -> BabyManagerData<R> {
+        self.config.dst_policy = dst;
+        self
+    }
+
+    /// Sets the child events are tagged with when the export has no `Child`
+    /// column or leaves it empty, e.g. when each child has their own export.
+    pub fn with_child(mut self, child: &str) -> BabyManagerData<R> {
+        self.child = Some(child.to_string());
+        self
+    }
+
+    /// The rows read and skipped so far. Only lenient parsing skips rows.
+    pub fn diagnostics(&self) -> &Diagnostics {
+        &self.diagnostics
+    }
+
+    /// Iterates over events along with the child each was recorded for.
+    pub fn child_events(&mut self) -> ChildIter<'_, R> {
+        ChildIter(self.into_iter())
+    }
 }
 
 impl<'a, R : io::Read> IntoIterator for &'a mut BabyManagerData<R> {
-    type Item = Result<Event, Box<Error>>;
+    type Item = Result<Event, Error>;
     type IntoIter = Iter<'a, R>;
     fn into_iter(self) -> Iter<'a, R> {
-        let state = match self.rdr.headers() {
+        let state = match self.rdr.byte_headers() {
             Err(e) => IterState::HeadersFailed(e.into()),
             Ok(hdrs) => IterState::HeadersParsed(hdrs.clone()),
         };
         Iter{
             rdr: &mut self.rdr,
-            state: state,
-            str_record: csv::StringRecord::new(),
+            mode: self.mode,
+            config: self.config,
+            child: self.child.clone(),
+            diagnostics: &mut self.diagnostics,
+            state,
+            record: csv::ByteRecord::new(),
         }
     }
 }
 
 pub struct Iter<'a, R: 'a> {
     rdr: &'a mut csv::Reader<R>,
+    mode: ParseMode,
+    config: ImportConfig,
+    child: Option<String>,
+    diagnostics: &'a mut Diagnostics,
     state: IterState,
-    str_record: csv::StringRecord,
+    // Read as bytes so that rows that aren't valid UTF-8 can still be
+    // reported when skipped.
+    record: csv::ByteRecord,
 }
 
 enum IterState {
-    HeadersFailed(Box<Error>),
-    HeadersParsed(csv::StringRecord),
+    HeadersFailed(Error),
+    HeadersParsed(csv::ByteRecord),
     Done,
 }
 
+impl<'a, R : io::Read> Iter<'a, R> {
+    fn read_event(&mut self, hdrs: &csv::ByteRecord) -> Option<Result<ChildEvent, Error>> {
+        match self.rdr.read_byte_record(&mut self.record) {
+            Err(e) => {
+                if !e.is_io_error() {
+                    self.diagnostics.rows_read += 1;
+                }
+                Some(Err(e.into()))
+            },
+            Ok(false) => None,
+            Ok(true) => {
+                self.diagnostics.rows_read += 1;
+                let line = self.record.position().map_or(0, |p| p.line());
+                Some(self.record.deserialize::<RawEvent>(Some(hdrs))
+                    .map_err(From::from)
+                    .and_then(|mut x| {
+                        x.line = line;
+                        x.config = self.config;
+                        let child = if x.child.is_empty() { self.child.clone() } else { Some(x.child.to_string()) };
+                        x.into_event().map(|event| ChildEvent{child, event})
+                    }))
+            }
+        }
+    }
+
+    // Records a row that failed to parse, returning the error back when it
+    // can't be skipped.
+    fn skip(&mut self, err: Error) -> Option<Error> {
+        if self.mode == ParseMode::Strict {
+            return Some(err);
+        }
+        if let Error::Csv(ref e) = err {
+            if e.is_io_error() {
+                return Some(err);
+            }
+        }
+        let line = err.line().or_else(|| self.record.position().map(|p| p.line())).unwrap_or(0);
+        let record = self.record.iter().map(|f| String::from_utf8_lossy(f).into_owned()).collect();
+        self.diagnostics.skipped.push(SkippedRow{
+            line,
+            record,
+            error: err,
+        });
+        None
+    }
+}
+
 impl<'a, R : io::Read> Iterator for Iter<'a, R> {
-    type Item = Result<Event, Box<Error>>;
+    type Item = Result<Event, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_child_event().map(|r| r.map(|ce| ce.event))
+    }
+}
+
+/// Iterates over events along with the child each was recorded for.
+pub struct ChildIter<'a, R: 'a>(Iter<'a, R>);
+
+impl<'a, R : io::Read> Iterator for ChildIter<'a, R> {
+    type Item = Result<ChildEvent, Error>;
     fn next(&mut self) -> Option<Self::Item> {
-        let s = std::mem::replace(&mut self.state, IterState::Done);
-        let (state, item) = match s {
-            IterState::HeadersFailed(e) => (IterState::Done, Some(Err(e))),
-            IterState::HeadersParsed(hdrs) => {
-                match self.rdr.read_record(&mut self.str_record) {
-                    Err(e) => (IterState::HeadersParsed(hdrs), Some(Err(e.into()))),
-                    Ok(false) => (IterState::Done, None),
-                    Ok(true) => {
-                        let res: Self::Item = self.str_record.deserialize::<RawEvent>(Some(&hdrs))
-                            .map_err(From::from)
-                            .and_then(|x| x.into_event());
-                        (IterState::HeadersParsed(hdrs), Some(res))
+        self.0.next_child_event()
+    }
+}
+
+impl<'a, R : io::Read> Iter<'a, R> {
+    fn next_child_event(&mut self) -> Option<Result<ChildEvent, Error>> {
+        loop {
+            let s = std::mem::replace(&mut self.state, IterState::Done);
+            let (state, item) = match s {
+                IterState::HeadersFailed(e) => (IterState::Done, Some(Err(e))),
+                IterState::HeadersParsed(hdrs) => {
+                    match self.read_event(&hdrs) {
+                        None => (IterState::Done, None),
+                        Some(Ok(event)) => (IterState::HeadersParsed(hdrs), Some(Ok(event))),
+                        Some(Err(e)) => match self.skip(e) {
+                            Some(e) => (IterState::HeadersParsed(hdrs), Some(Err(e))),
+                            None => {
+                                self.state = IterState::HeadersParsed(hdrs);
+                                continue;
+                            },
+                        },
                     }
-                }
-            },
-            IterState::Done => (IterState::Done, None),
-        };
-        self.state = state;
-        item
+                },
+                IterState::Done => (IterState::Done, None),
+            };
+            self.state = state;
+            return item;
+        }
     }
 }
@@ -1,372 +1,894 @@
+extern crate babystats;
+extern crate chrono;
 extern crate csv;
-#[macro_use]
-extern crate serde_derive;
-#[macro_use]
-extern crate lazy_static;
 extern crate serde;
-extern crate chrono;
-extern crate regex;
-
-use std::collections::BTreeMap;
-use std::error::Error;
+extern crate serde_json;
+
+use babystats::{BabyManagerData,BabyManagerWriter,BabyProfile,ChildEvent,Clock,DateOrder,DateRange,DecimalSeparator,
+                DiaperAlertKind,DiaperConfig,DiaperEvent,DiaperQuery,Event,FeedingConfig,FeedingEvent,GrowthChart,
+                GrowthMeasure,GrowthReference,ImportConfig,Metric,NextSideConfig,NightConfig,ParseMode,Period,
+                PumpingEvent,RollingConfig,Schedule,Sex,SummaryConfig,SummaryTable,UnitSystem,Volume,
+                DEFAULT_SESSION_GAP_MINUTES};
+use chrono::{Local,NaiveDate};
+use serde::Serialize;
+use std::collections::{BTreeMap,BTreeSet};
+use std::env;
 use std::io;
+use std::io::Read;
 use std::process;
-use chrono::offset::{Local,Utc};
-use chrono::TimeZone;
-use regex::Regex;
-
-#[derive(Debug,Deserialize)]
-struct RawRecord {
-    #[serde(rename = "Type")]
-	typ: String,
-    #[serde(rename = "Start")]
-	start: String,
-    #[serde(rename = "End")]
-	end: String,
-    #[serde(rename = "Duration")]
-	duration: String,
-    #[serde(rename = "Extra")]
-	extra: String,
-    #[serde(rename = "Extra2")]
-	extra2: String,
-    #[serde(rename = "Note")]
-	note: String,
-}
-
-impl RawRecord {
-    fn into_record(self) -> Result<Record, Box<Error>> {
-        match self.typ.as_str() {
-            "Sleep" => Ok(Record::Sleep(self.to_sleep_record()?)),
-            "Diaper" => Ok(Record::Diaper(self.to_diaper_record()?)),
-            "Bottle feeding" => Ok(Record::Feeding(FeedingRecord::Bottle(self.to_bottle_record()?))),
-            "Left breast" => Ok(Record::Feeding(FeedingRecord::LeftBreast(self.to_breast_record()?))),
-            "Right breast" => Ok(Record::Feeding(FeedingRecord::RightBreast(self.to_breast_record()?))),
-            "Pumping" => Ok(Record::Pumping(self.to_pumping_record()?)),
-            "Vaccination" => Ok(Record::TummyTime(self.to_tummy_time_record()?)),
-            "Measure" => Ok(Record::Measure(self.to_measure_record()?)),
-            "Note" => Ok(Record::Note(self.to_note_record()?)),
-            _ => Err(From::from(format!("unknown type: {}", self.typ))),
+use std::str::FromStr;
+use std::error::Error;
+
+const USAGE: &str = "\
+usage: babystats COMMAND [OPTIONS] [[NAME=]FILE...]
+
+Reads Baby Manager exports from the given files, or from stdin. The date,
+time and number formats of each are detected from its first rows, unless
+given. Events are recorded for the child named in a Child column, or else the
+NAME the file is given with. With events for several children, choose one
+with --child.
+
+commands:
+  sleep           longest and total sleep per day
+  wakeups         wakeups and sleep stretches per night
+  pumping         pumping output per day
+  pump-times      the mean pumping output by time of day
+  pump-check      pumping sessions whose sides don't add up to the total
+  feeding         feeds per day
+  feed-times      the mean time between feeds by time of day
+  clusters        cluster feeding episodes
+  intake          bottle intake by type of milk per day
+  sides           breastfeeding time per side, per day or period
+  next-side       which breast to offer next
+  diapers         diaper changes per day
+  diaper-alerts   periods with too few wet diapers or no stool
+  diaper-log      diaper changes matching --match, with their details
+  stools          stool color, consistency and amount, and rashes, per day or period
+  growth          measurements, weight gain and, with --birth and --sex, percentiles
+  crossings       measurements crossing percentile lines, given --birth and --sex
+  summary         a summary of each day
+  trend           rolling statistics of a daily metric
+  periods         a daily metric by week, month or custom period
+  vaccinations    vaccination history against a schedule
+  compare         a daily metric for each child side by side
+  merge           combine exports, dropping duplicates, for every child unless --child is given
+
+options:
+  --from DATE         ignore events before DATE, as YYYY-MM-DD
+  --to DATE           ignore events after DATE
+  --tz ZONE           timezone of the export: local, utc, +05:30 or an IANA name
+  --dst POLICY        DST gaps and overlaps: earliest, latest or error
+  --format FORMAT     text, json or csv
+  --units UNITS       imperial or metric, to show and write quantities in (the units of the
+                      first export)
+  --input-units UNITS imperial or metric, for quantities in the exports without a unit
+  --date-order ORDER  the order of dates in the exports: dmy, mdy or ymd
+  --clock CLOCK       the clock of times in the exports: 24h or 12h
+  --decimal SEP       the decimal separator in the exports: point or comma
+  --average DAYS      sleep: days to average the longest sleep over (5)
+  --gap MINUTES       sleep, summary: the longest brief wake within one sleep (15)
+  --bedtime WINDOW    wakeups, summary: when nights start (18:00-02:00)
+  --wake WINDOW       wakeups, summary: when nights end (04:00-10:00)
+  --max-gap MINUTES   wakeups, summary: the longest time awake within a night (90)
+  --same-feed MINUTES feeding: feeds this close together count as one (10)
+  --hours HOURS       feed-times, pump-times: the hours in each time of day (4)
+  --cluster-feeds N   clusters: the number of feeds that make a cluster (5)
+  --cluster-hours H   clusters: the hours those feeds fall within (3)
+  --balance HOURS     next-side: the hours to judge the balance between sides over (24)
+  --threshold RATIO   next-side: the imbalance, from 0 to 1, to correct for (0.2)
+  --tolerance VOLUME  pump-check: the difference to allow, in ml unless given in oz (5)
+  --min-wet N         diaper-alerts: the fewest wet diapers expected in 24 hours (6)
+  --stool-days DAYS   diaper-alerts: the most days expected without stool (3)
+  --match DETAILS     diaper-log: comma separated details to match, e.g. dirty,green or rash
+  --metric METRIC     trend, periods, compare: longest-sleep, total-sleep, feeds, feed-interval,
+                      bottle-volume, formula-volume, breast-milk-share, diapers,
+                      wet-diapers, dirty-diapers, pumped or pumping-sessions
+  --days DAYS         trend: the number of days in each window (7)
+  --align ALIGNMENT   trend: trailing or centered
+  --missing POLICY    trend: days without data are skipped, zero or a gap
+  --period PERIOD     periods, sides, stools: week, month, a number of days such as 14d,
+                      or comma separated start dates (periods: week, others: day)
+  --window MINUTES    merge: how close near-duplicates start (5)
+  --profile FILE      a TOML or JSON file giving the baby's name, birth date, sex and
+                      gestational age, used for --birth and --sex and to label ages
+  --child NAME        the child to analyze, and to use from a profile file with several
+  --birth DATE        vaccinations, growth, crossings: the baby's birth date
+  --as-of DATE        vaccinations: the date to judge overdue doses on (today)
+  --sex SEX           growth, crossings: male or female
+  --cdc MEASURE=FILE  growth, crossings: a CDC LMS table such as wtage.csv, needed for
+                      percentiles after 24 months as only the WHO tables are built in,
+                      where MEASURE is weight, height or head; may be repeated
+  --schedule FILE     vaccinations: a JSON schedule to use instead of the CDC's";
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("unknown format {}; expecting text, json or csv", s)),
         }
     }
+}
+
+struct Options {
+    command: String,
+    paths: Vec<String>,
+    range: DateRange,
+    /// The timezone and DST policy of the inputs.
+    config: ImportConfig,
+    // The formats of the inputs, where given rather than detected.
+    date_order: Option<DateOrder>,
+    clock: Option<Clock>,
+    decimal: Option<DecimalSeparator>,
+    input_units: Option<UnitSystem>,
+    /// The units to show and write quantities in.
+    units: UnitSystem,
+    format: Format,
+    average: usize,
+    gap: chrono::Duration,
+    night: NightConfig,
+    feeding: FeedingConfig,
+    hours: u32,
+    tolerance: Volume,
+    diapers: DiaperConfig,
+    query: DiaperQuery,
+    metric: Option<Metric>,
+    rolling: RollingConfig,
+    period: Option<Period>,
+    next_side: NextSideConfig,
+    window: chrono::Duration,
+    profile: Option<BabyProfile>,
+    child: Option<String>,
+    birth: Option<NaiveDate>,
+    as_of: Option<NaiveDate>,
+    sex: Option<Sex>,
+    cdc: Vec<(GrowthMeasure, String)>,
+    schedule: Option<String>,
+}
 
-    fn to_sleep_record(&self) -> Result<SleepRecord, Box<Error>> {
-        Ok(SleepRecord{
-            start: datetime_from_str(&self.start)?,
-            end:
-                if self.end.len() == 0 {
-                    None
-                } else {
-                    Some(datetime_from_str(&self.end)?)
+impl Options {
+    fn parse<I: Iterator<Item=String>>(mut args: I) -> Result<Options, Box<dyn Error>> {
+        let mut opts = Options{
+            command: args.next().ok_or(USAGE)?,
+            paths: Vec::new(),
+            range: DateRange::default(),
+            config: ImportConfig::default(),
+            date_order: None,
+            clock: None,
+            decimal: None,
+            input_units: None,
+            units: UnitSystem::default(),
+            format: Format::Text,
+            average: 5,
+            gap: chrono::Duration::minutes(DEFAULT_SESSION_GAP_MINUTES),
+            night: NightConfig::default(),
+            feeding: FeedingConfig::default(),
+            hours: 4,
+            tolerance: Volume::from_ml(5.0),
+            diapers: DiaperConfig::default(),
+            query: DiaperQuery::default(),
+            metric: None,
+            rolling: RollingConfig::default(),
+            period: None,
+            next_side: NextSideConfig::default(),
+            window: chrono::Duration::minutes(5),
+            profile: None,
+            child: None,
+            birth: None,
+            as_of: None,
+            sex: None,
+            cdc: Vec::new(),
+            schedule: None,
+        };
+        let mut profile_path = None;
+        let mut units = None;
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                opts.paths.push(arg);
+                continue;
+            }
+            let value = args.next().ok_or_else(|| format!("missing value for {}", arg))?;
+            match arg.as_str() {
+                "--from" => opts.range.from = Some(parse_date(&value)?),
+                "--to" => opts.range.to = Some(parse_date(&value)?),
+                "--tz" => opts.config.timezone = value.parse()?,
+                "--dst" => opts.config.dst_policy = value.parse()?,
+                "--format" => opts.format = value.parse()?,
+                "--units" => units = Some(value.parse()?),
+                "--input-units" => opts.input_units = Some(value.parse()?),
+                "--date-order" => opts.date_order = Some(value.parse()?),
+                "--clock" => opts.clock = Some(value.parse()?),
+                "--decimal" => opts.decimal = Some(value.parse()?),
+                "--average" => opts.average = value.parse()?,
+                "--gap" => opts.gap = chrono::Duration::minutes(value.parse()?),
+                "--bedtime" => opts.night.bedtime = value.parse()?,
+                "--wake" => opts.night.wake = value.parse()?,
+                "--max-gap" => opts.night.max_gap = chrono::Duration::minutes(value.parse()?),
+                "--same-feed" => opts.feeding.same_feed = chrono::Duration::minutes(value.parse()?),
+                "--hours" => opts.hours = value.parse()?,
+                "--tolerance" => opts.tolerance = Volume::parse(&value, UnitSystem::Metric).
+                    ok_or_else(|| format!("invalid volume {}", value))?,
+                "--cluster-feeds" => opts.feeding.cluster_feeds = value.parse()?,
+                "--cluster-hours" => opts.feeding.cluster_window = chrono::Duration::hours(value.parse()?),
+                "--min-wet" => opts.diapers.min_wet = value.parse()?,
+                "--stool-days" => opts.diapers.max_without_stool = chrono::Duration::days(value.parse()?),
+                "--match" => opts.query = value.parse()?,
+                "--metric" => opts.metric = Some(value.parse()?),
+                "--days" => opts.rolling.days = value.parse()?,
+                "--align" => opts.rolling.alignment = value.parse()?,
+                "--missing" => opts.rolling.missing = value.parse()?,
+                "--period" => opts.period = Some(value.parse()?),
+                "--balance" => opts.next_side.window = chrono::Duration::hours(value.parse()?),
+                "--threshold" => opts.next_side.threshold = value.parse()?,
+                "--window" => opts.window = chrono::Duration::minutes(value.parse()?),
+                "--profile" => profile_path = Some(value),
+                "--child" => opts.child = Some(value),
+                "--birth" => opts.birth = Some(parse_date(&value)?),
+                "--as-of" => opts.as_of = Some(parse_date(&value)?),
+                "--sex" => opts.sex = Some(value.parse()?),
+                "--cdc" => {
+                    let (measure, path) = value.split_at(value.find('=').ok_or("expecting --cdc MEASURE=FILE")?);
+                    opts.cdc.push((measure.parse()?, path[1..].to_string()));
+                },
+                "--schedule" => opts.schedule = Some(value),
+                _ => return Err(From::from(format!("unknown option {}\n\n{}", arg, USAGE))),
+            }
+        }
+        // Unless given, quantities are shown in the units of the first export.
+        opts.units = match (units, opts.paths.first()) {
+            (Some(units), _) => units,
+            (None, Some(path)) => {
+                opts.import_config(ImportConfig::detect_from_path(split_source(path).1, DETECT_ROWS)?).units
+            },
+            (None, None) => opts.input_units.unwrap_or_default(),
+        };
+        if let Some(path) = profile_path {
+            let mut profiles = babystats::profiles_from_path(&path)?;
+            opts.profile = Some(match opts.child {
+                Some(ref name) => {
+                    let i = profiles.iter().position(|p| p.name == *name).
+                        ok_or_else(|| format!("no child named {} in {}", name, path))?;
+                    profiles.swap_remove(i)
                 },
-            duration: self.to_duration()?,
-            note: self.note.clone(),
-        })
+                None if profiles.len() == 1 => profiles.remove(0),
+                None => return Err(From::from(format!("{} has several children; choose one with --child NAME", path))),
+            });
+        }
+        Ok(opts)
     }
 
-    fn to_diaper_record(&self) -> Result<DiaperRecord, Box<Error>> {
-        Ok(DiaperRecord{
-            time: datetime_from_str(&self.start)?,
-            pee: self.extra.contains("Urine"),
-            poo: self.extra.contains("Feces"),
-            note: self.note.clone(),
-        })
+    fn units(&self) -> UnitSystem {
+        self.units
     }
 
-    fn to_bottle_record(&self) -> Result<BottleRecord, Box<Error>> {
-        Ok(BottleRecord{
-            time: datetime_from_str(&self.start)?,
-            milk: match self.extra2.as_str() {
-                "Mom's milk" => Milk::BreastMilk,
-                "Formula" => Milk::Formula,
-                _ => Milk::Unknown,
-            },
-            ounces: {
-                if self.extra.ends_with(" oz") {
-                    self.extra[..self.extra.len()-3].parse::<f32>()?
-                } else {
-                    0.0
-                }
-            },
-            note: self.note.clone(),
-        })
+    // The formats of an input, as detected from its first rows unless given.
+    fn import_config(&self, detected: ImportConfig) -> ImportConfig {
+        ImportConfig{
+            date_order: self.date_order.unwrap_or(detected.date_order),
+            clock: self.clock.unwrap_or(detected.clock),
+            decimal_separator: self.decimal.unwrap_or(detected.decimal_separator),
+            units: self.input_units.unwrap_or(detected.units),
+            ..self.config
+        }
     }
 
-    fn to_breast_record(&self) -> Result<BreastRecord, Box<Error>> {
-        Ok(BreastRecord{
-            start: datetime_from_str(&self.start)?,
-            end:
-                if self.end.len() == 0 {
-                    None
-                } else {
-                    Some(datetime_from_str(&self.end)?)
-                },
-            duration: self.to_duration()?,
-            note: self.note.clone(),
-        })
+    fn birth(&self) -> Option<NaiveDate> {
+        self.birth.or_else(|| self.profile.as_ref().map(|p| p.birth))
     }
+}
 
-    fn to_pumping_record(&self) -> Result<PumpingRecord, Box<Error>> {
-        Ok(PumpingRecord{
-            start: datetime_from_str(&self.start)?,
-            ounces: {
-                if self.extra.ends_with(" oz") {
-                    self.extra[..self.extra.len()-3].parse::<f32>()?
-                } else {
-                    0.0
-                }
-            },
-            note: self.note.clone(),
-        })
-    }
+// The number of rows of each input to detect its formats from.
+const DETECT_ROWS: usize = 100;
 
-    fn to_tummy_time_record(&self) -> Result<TummyTimeRecord, Box<Error>> {
-        Ok(TummyTimeRecord{
-            start: datetime_from_str(&self.start)?,
-            end:
-                if self.end.len() == 0 {
-                    None
-                } else {
-                    Some(datetime_from_str(&self.end)?)
-                },
-            duration: self.to_duration()?,
-            note: self.note.clone(),
-        })
+fn parse_date(s: &str) -> Result<NaiveDate, chrono::ParseError> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+}
+
+// Splits an input given as NAME=FILE into the child's name and the file.
+fn split_source(path: &str) -> (Option<&str>, &str) {
+    match path.find('=') {
+        Some(i) => (Some(&path[..i]), &path[i + 1..]),
+        None => (None, path),
     }
+}
 
-    fn to_measure_record(&self) -> Result<MeasureRecord, Box<Error>> {
-        lazy_static! {
-            static ref WEIGHT_RE: Regex = Regex::new(r"Weight: (\d+(?:\.\d+)?) lb").unwrap();
-            static ref HEIGHT_RE: Regex = Regex::new(r"Height: (\d+(?:\.\d+)?) in").unwrap();
-            static ref HEAD_CIRC_RE: Regex = Regex::new(r"Head circumference: (\d+(?:\.\d+)?) in").unwrap();
+// Reads the events from each input, tagged with the child they were recorded
+// for, printing what was skipped to stderr.
+fn read_child_sources(opts: &Options) -> Result<Vec<Vec<ChildEvent>>, Box<dyn Error>> {
+    let in_range = |events: Vec<ChildEvent>| -> Vec<ChildEvent> {
+        events.into_iter().filter(|ce| opts.range.contains(ce.event.time().date_naive())).collect()
+    };
+    if opts.paths.is_empty() {
+        let mut input = Vec::new();
+        io::stdin().read_to_end(&mut input)?;
+        let config = opts.import_config(ImportConfig::detect(&input[..], DETECT_ROWS)?);
+        let mut rdr = BabyManagerData::from_reader(&input[..]).
+            with_mode(ParseMode::Lenient).
+            with_config(config);
+        let events = rdr.child_events().collect::<Result<Vec<_>, _>>()?;
+        eprint!("{}", rdr.diagnostics());
+        return Ok(vec![in_range(events)]);
+    }
+    let mut sources = Vec::new();
+    for path in &opts.paths {
+        let (child, file) = split_source(path);
+        let config = opts.import_config(ImportConfig::detect_from_path(file, DETECT_ROWS)?);
+        let mut rdr = BabyManagerData::from_path(file)?.
+            with_mode(ParseMode::Lenient).
+            with_config(config);
+        if let Some(child) = child {
+            rdr = rdr.with_child(child);
         }
-        let weight = WEIGHT_RE.
-            captures(self.extra.as_str()).
-            and_then(|x| {
-                x.get(1).unwrap().as_str().parse::<f32>().ok()
-            });
-        let height = HEIGHT_RE.
-            captures(self.extra.as_str()).
-            and_then(|x| {
-                x.get(1).unwrap().as_str().parse::<f32>().ok()
-            });
-        let head_circ = HEAD_CIRC_RE.
-            captures(self.extra.as_str()).
-            and_then(|x| {
-                x.get(1).unwrap().as_str().parse::<f32>().ok()
-            });
-        Ok(MeasureRecord{
-            time: datetime_from_str(&self.start)?,
-            weight: weight,
-            height: height,
-            head_circ: head_circ,
-            note: self.note.clone(),
-        })
+        let events = rdr.child_events().collect::<Result<Vec<_>, _>>()?;
+        eprint!("{}: {}", file, rdr.diagnostics());
+        sources.push(in_range(events));
     }
+    Ok(sources)
+}
 
-    fn to_note_record(&self) -> Result<NoteRecord, Box<Error>> {
-        Ok(NoteRecord{
-            time: datetime_from_str(&self.start)?,
-            note: self.note.clone(),
-        })
+// Reads the events from each input for the child chosen with --child, which
+// is needed when there are events for several children.
+fn read_sources(opts: &Options) -> Result<Vec<Vec<Event>>, Box<dyn Error>> {
+    let sources = read_child_sources(opts)?;
+    if let Some(ref child) = opts.child {
+        return Ok(sources.into_iter().map(|events| babystats::filter_child(events, child)).collect());
     }
+    let children: BTreeSet<String> = sources.iter().flat_map(|events| babystats::children(events)).collect();
+    if children.len() > 1 {
+        let children: Vec<String> = children.into_iter().collect();
+        return Err(From::from(format!("events for several children ({}); choose one with --child NAME, \
+                                       or compare them with compare", children.join(", "))));
+    }
+    Ok(sources.into_iter().map(|events| events.into_iter().map(|ce| ce.event).collect()).collect())
+}
 
-    fn to_duration(&self) -> Result<chrono::Duration, Box<Error>> {
-        let v: Vec<&str> = self.duration.split(':').collect();
-        if v.len() != 2 {
-            return Err(From::from(format!("Unable to parse duration {}; Expecting HH:MM format", self.duration)))
+// Groups the events from each input by child, keeping the inputs apart so
+// that merging them only drops events found in more than one.
+fn sources_by_child(sources: Vec<Vec<ChildEvent>>) -> BTreeMap<Option<String>, Vec<Vec<Event>>> {
+    let mut groups = BTreeMap::new();
+    for events in sources {
+        for (child, group) in babystats::group_by_child(events) {
+            groups.entry(child).or_insert_with(Vec::new).push(group);
         }
-        let hours : i64 = v[0].parse()?;
-        let minutes : i64 = v[1].parse()?;
-        Ok(chrono::Duration::minutes(hours * 60 + minutes))
     }
+    groups
 }
 
-fn datetime_from_str<T: AsRef<str>>(s: T) -> Result<chrono::DateTime<Local>, Box<Error>> {
-    Ok(Utc.datetime_from_str(s.as_ref(), "%0d/%0m/%Y %H:%M")?.with_timezone(&Local))
+// Reads all inputs into one time ordered stream, dropping events found in
+// more than one input.
+fn read_events(opts: &Options) -> Result<Vec<Event>, Box<dyn Error>> {
+    Ok(merge_sources(read_sources(opts)?))
 }
 
-#[derive(Debug,Clone)]
-enum Record {
-    Sleep(SleepRecord),
-    Diaper(DiaperRecord),
-    Feeding(FeedingRecord),
-    Pumping(PumpingRecord),
-    TummyTime(TummyTimeRecord),
-    Measure(MeasureRecord),
-    Note(NoteRecord),
+// Merges the events from each input, printing how many were dropped for
+// being found in more than one.
+fn merge_sources(sources: Vec<Vec<Event>>) -> Vec<Event> {
+    let report = babystats::merge(sources, chrono::Duration::zero());
+    if report.exact_duplicates > 0 {
+        eprintln!("dropped {} events found in more than one input", report.exact_duplicates);
+    }
+    report.events
 }
 
-impl Record {
-    fn time(&self) -> chrono::DateTime<Local> {
-        match self {
-            &Record::Sleep(ref r) => r.start,
-            &Record::Diaper(ref r) => r.time,
-            &Record::Feeding(ref r) => r.time(),
-            &Record::Pumping(ref r) => r.start,
-            &Record::TummyTime(ref r) => r.start,
-            &Record::Measure(ref r) => r.time,
-            &Record::Note(ref r) => r.time,
-        }
+fn emit<T, F>(format: Format, rows: &[T], text: F) -> Result<(), Box<dyn Error>>
+    where T: Serialize, F: Fn(&T) -> String {
+    match format {
+        Format::Text => {
+            for row in rows {
+                println!("{}", text(row));
+            }
+        },
+        Format::Json => {
+            serde_json::to_writer_pretty(io::stdout(), rows)?;
+            println!();
+        },
+        Format::Csv => {
+            let mut wtr = csv::Writer::from_writer(io::stdout());
+            for row in rows {
+                wtr.serialize(row)?;
+            }
+            wtr.flush()?;
+        },
     }
+    Ok(())
 }
 
-#[derive(Debug,Clone)]
-struct SleepRecord {
-    start: chrono::DateTime<Local>,
-    end: Option<chrono::DateTime<Local>>,
-    duration: chrono::Duration,
-    note: String,
+fn duration_str(d: chrono::Duration) -> String {
+    format!("{:02}:{:02}", d.num_hours(), d.num_minutes() % 60)
 }
 
-#[derive(Debug,Clone)]
-struct DiaperRecord {
-    time: chrono::DateTime<Local>,
-    pee: bool,
-    poo: bool,
-    note: String,
+fn sleep(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let sleeps: Vec<_> = read_events(opts)?.into_iter().filter_map(|e| match e {
+        Event::Sleep(se) => Some(se),
+        _ => None,
+    }).collect();
+    let sessions = babystats::sleep_sessions(&sleeps, opts.gap);
+    let days = babystats::sleep_by_date(&sessions, opts.average);
+    emit(opts.format, &days, |d| {
+        format!("{}: longest {} ({} day average {}), total {} in {} sleeps",
+                d.date, duration_str(d.longest), opts.average, duration_str(d.longest_average),
+                duration_str(d.total), d.sessions)
+    })
 }
 
-#[derive(Debug,Clone)]
-enum FeedingRecord {
-    Bottle(BottleRecord),
-    LeftBreast(BreastRecord),
-    RightBreast(BreastRecord),
+fn wakeups(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let sleeps: Vec<_> = read_events(opts)?.into_iter().filter_map(|e| match e {
+        Event::Sleep(se) => Some(se),
+        _ => None,
+    }).collect();
+    let nights = babystats::nights(&sleeps, &opts.night);
+    emit(opts.format, &nights, |n| {
+        let windows: Vec<String> = n.wake_windows.iter().map(|&d| duration_str(d)).collect();
+        format!("{}: {} wakeups ({}), slept {} from {} to {}, longest stretch {}", n.date, n.wakeups,
+                windows.join(", "), duration_str(n.total_sleep), n.bedtime.format("%H:%M"),
+                n.wake_time.format("%H:%M"), duration_str(n.longest_stretch))
+    })
 }
 
-impl FeedingRecord {
-    fn time(&self) -> chrono::DateTime<Local> {
-        match self {
-            &FeedingRecord::Bottle(ref r) => r.time,
-            &FeedingRecord::LeftBreast(ref r) => r.start,
-            &FeedingRecord::RightBreast(ref r) => r.start,
-        }
-    }
+fn read_pumping(opts: &Options) -> Result<Vec<PumpingEvent>, Box<dyn Error>> {
+    Ok(read_events(opts)?.into_iter().filter_map(|e| match e {
+        Event::Pumping(pe) => Some(pe),
+        _ => None,
+    }).collect())
+}
+
+fn pumping(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let units = opts.units();
+    let days = babystats::pumping_by_date(&read_pumping(opts)?);
+    emit(opts.format, &days, |d| {
+        format!("{}: {} in {} sessions, {} per session (L {}, R {})", d.date, d.volume.display(units), d.sessions,
+                d.mean_per_session.display(units), d.left.display(units), d.right.display(units))
+    })
 }
 
-#[derive(Debug,Clone)]
-enum Milk {
-    BreastMilk,
-    Formula,
-    Unknown,
+fn pump_times(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let units = opts.units();
+    let times = babystats::yield_by_time_of_day(&read_pumping(opts)?, opts.hours);
+    emit(opts.format, &times, |t| {
+        let mean = t.mean.map_or("-".to_string(), |v| v.display(units).to_string());
+        format!("{:02}:00-{:02}:00: {} per session ({} sessions)", t.start_hour, t.end_hour, mean, t.sessions)
+    })
 }
 
-#[derive(Debug,Clone)]
-struct BottleRecord {
-    time: chrono::DateTime<Local>,
-    milk: Milk,
-    ounces: f32,
-    note: String,
+fn pump_check(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let units = opts.units();
+    let mismatches = babystats::pumping_mismatches(&read_pumping(opts)?, opts.tolerance);
+    emit(opts.format, &mismatches, |m| {
+        format!("{}: L {} + R {} is {} but the total is {}", m.start.format("%Y-%m-%d %H:%M"),
+                m.left.display(units), m.right.display(units), (m.left + m.right).display(units),
+                m.volume.display(units))
+    })
 }
 
-#[derive(Debug,Clone)]
-struct BreastRecord {
-    start: chrono::DateTime<Local>,
-    end: Option<chrono::DateTime<Local>>,
-    duration: chrono::Duration,
-    note: String,
+fn read_feeds(opts: &Options) -> Result<Vec<FeedingEvent>, Box<dyn Error>> {
+    Ok(read_events(opts)?.into_iter().filter_map(|e| match e {
+        Event::Feeding(fe) => Some(fe),
+        _ => None,
+    }).collect())
 }
 
-#[derive(Debug,Clone)]
-struct PumpingRecord {
-    start: chrono::DateTime<Local>,
-    ounces: f32,
-    note: String,
+fn feeding(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let units = opts.units();
+    let days = babystats::feeding_by_date(&read_feeds(opts)?, &opts.feeding);
+    emit(opts.format, &days, |d| {
+        let interval = d.mean_interval.map_or(String::new(), |i| format!(", every {}", duration_str(i)));
+        format!("{}: {} feeds{}, {} bottles ({}), breast L {} R {}", d.date, d.feeds, interval, d.bottles,
+                d.bottle_volume.display(units), duration_str(d.left_breast), duration_str(d.right_breast))
+    })
 }
 
-#[derive(Debug,Clone)]
-struct TummyTimeRecord {
-    start: chrono::DateTime<Local>,
-    end: Option<chrono::DateTime<Local>>,
-    duration: chrono::Duration,
-    note: String,
+fn feed_times(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let feeds = babystats::feeds(&read_feeds(opts)?, &opts.feeding);
+    let intervals = babystats::feed_intervals(&feeds);
+    let times = babystats::intervals_by_time_of_day(&intervals, opts.hours);
+    emit(opts.format, &times, |t| {
+        let mean = t.mean.map_or("-".to_string(), duration_str);
+        format!("{:02}:00-{:02}:00: every {} ({} intervals)", t.start_hour, t.end_hour, mean, t.intervals)
+    })
 }
 
-#[derive(Debug,Clone)]
-struct MeasureRecord {
-    time: chrono::DateTime<Local>,
-    weight: Option<f32>,
-    height: Option<f32>,
-    head_circ: Option<f32>,
-    note: String,
+fn clusters(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let feeds = babystats::feeds(&read_feeds(opts)?, &opts.feeding);
+    let clusters = babystats::cluster_feeds(&feeds, &opts.feeding);
+    emit(opts.format, &clusters, |c| {
+        format!("{} to {}: {} feeds", c.start.format("%Y-%m-%d %H:%M"), c.end.format("%H:%M"), c.feeds)
+    })
 }
 
-#[derive(Debug,Clone)]
-struct NoteRecord {
-    time: chrono::DateTime<Local>,
-    note: String,
+fn intake(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let units = opts.units();
+    let days = babystats::intake_by_date(&read_feeds(opts)?);
+    emit(opts.format, &days, |d| {
+        let types = [("breast milk", d.breast_milk), ("formula", d.formula), ("mixed", d.mixed),
+                     ("donor", d.donor), ("other", d.other), ("unknown", d.unknown)];
+        let parts: Vec<String> = types.iter().filter(|&&(_, v)| v.ml() > 0.0).
+            map(|&(name, v)| format!("{} {}", name, v.display(units))).
+            collect();
+        let share = d.breast_milk_share.map_or(String::new(), |s| format!(", {:.0}% breast milk", s * 100.0));
+        format!("{}: {} in {} bottles ({}){}", d.date, d.total.display(units), d.bottles, parts.join(", "), share)
+    })
 }
 
-struct BabyManagerData<R> {
-    rdr: csv::Reader<R>
+fn sides(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let events = read_feeds(opts)?;
+    let balance = match opts.period {
+        Some(ref period) => babystats::side_balance_by_period(&events, period),
+        None => babystats::side_balance_by_date(&events),
+    };
+    emit(opts.format, &balance, |b| {
+        let imbalance = b.imbalance.map_or(String::new(), |i| format!(" ({:+.0}% {})", i.abs() * 100.0,
+                                                                      if i >= 0.0 { "left" } else { "right" }));
+        format!("{}: L {} R {}{}", b.label, duration_str(b.left), duration_str(b.right), imbalance)
+    })
 }
 
-impl<R: io::Read> BabyManagerData<R> {
-    fn from_reader(rdr: R) -> BabyManagerData<R> {
-        BabyManagerData{
-            rdr: csv::Reader::from_reader(rdr),
+fn next_side(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let next = babystats::next_side(&read_feeds(opts)?, &opts.next_side).ok_or("no breast feeds recorded")?;
+    emit(opts.format, &[next], |n| {
+        let reason = if n.rebalancing {
+            format!("the {} side has had less time recently (L {}, R {})", n.side,
+                    duration_str(n.recent_left), duration_str(n.recent_right))
+        } else {
+            format!("the last feed was on the {} side, starting at {}", n.last_side,
+                    n.last_time.format("%Y-%m-%d %H:%M"))
+        };
+        format!("offer the {} side next; {}", n.side, reason)
+    })
+}
+
+fn read_diapers(opts: &Options) -> Result<Vec<DiaperEvent>, Box<dyn Error>> {
+    Ok(read_events(opts)?.into_iter().filter_map(|e| match e {
+        Event::Diaper(de) => Some(de),
+        _ => None,
+    }).collect())
+}
+
+fn diapers(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let days = babystats::diapers_by_date(&read_diapers(opts)?);
+    emit(opts.format, &days, |d| {
+        let gap = d.longest_wet_gap.map_or(String::new(), |g| format!(", longest {} between wet", duration_str(g)));
+        format!("{}: {} changes, {} wet, {} dirty{}", d.date, d.changes, d.wet, d.dirty, gap)
+    })
+}
+
+fn diaper_alerts(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let alerts = babystats::diaper_alerts(&read_diapers(opts)?, &opts.diapers);
+    emit(opts.format, &alerts, |a| {
+        let span = format!("{} to {}", a.start.format("%Y-%m-%d %H:%M"), a.end.format("%Y-%m-%d %H:%M"));
+        match a.kind {
+            DiaperAlertKind::FewWet => format!("{}: only {} wet diapers in 24 hours", span, a.count),
+            DiaperAlertKind::NoStool => format!("{}: no stool for {}", span, duration_str(a.end - a.start)),
         }
-    }
+    })
+}
+
+fn diaper_log(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let rows = babystats::diaper_log(&read_diapers(opts)?, &opts.query);
+    emit(opts.format, &rows, |r| {
+        let kind = match (r.wet, r.dirty) {
+            (true, true) => "wet and dirty",
+            (true, false) => "wet",
+            (false, true) => "dirty",
+            (false, false) => "dry",
+        };
+        let mut details: Vec<&str> = [&r.color, &r.consistency, &r.amount].iter().
+            filter(|s| !s.is_empty()).
+            map(|s| s.as_str()).
+            collect();
+        if r.rash {
+            details.push("rash");
+        }
+        if r.cream {
+            details.push("cream");
+        }
+        let detail = if details.is_empty() { String::new() } else { format!(" ({})", details.join(", ")) };
+        let note = if r.note.is_empty() { String::new() } else { format!(": {}", r.note) };
+        format!("{}: {}{}{}", r.time.format("%Y-%m-%d %H:%M"), kind, detail, note)
+    })
 }
 
-impl<'a, R : io::Read> IntoIterator for &'a mut BabyManagerData<R> {
-    type Item = Result<Record, Box<Error>>;
-    type IntoIter = Iter<'a, R>;
-    fn into_iter(self) -> Iter<'a, R> {
-        Iter{iter: self.rdr.deserialize::<RawRecord>()}
+fn stools(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let summaries = babystats::stool_summary(&read_diapers(opts)?, opts.period.as_ref());
+    emit(opts.format, &summaries, |s| {
+        let counts = |counts: Vec<String>| if counts.is_empty() { "-".to_string() } else { counts.join(", ") };
+        format!("{}: {} dirty of {} changes; color {}; consistency {}; amount {}; {} rashes, cream {} times",
+                s.label, s.dirty, s.changes,
+                counts(s.colors.iter().map(|(k, n)| format!("{} {}", k, n)).collect()),
+                counts(s.consistencies.iter().map(|(k, n)| format!("{} {}", k, n)).collect()),
+                counts(s.amounts.iter().map(|(k, n)| format!("{} {}", k, n)).collect()),
+                s.rashes, s.creams)
+    })
+}
+
+// The chart to compare measurements against, when a birth date and sex are
+// given. A profile's birth date is corrected for babies born preterm.
+fn growth_chart(opts: &Options) -> Result<Option<GrowthChart>, Box<dyn Error>> {
+    let birth = opts.birth.or_else(|| opts.profile.as_ref().map(|p| p.due()));
+    let sex = opts.sex.or_else(|| opts.profile.as_ref().and_then(|p| p.sex));
+    let (birth, sex) = match (birth, sex) {
+        (Some(birth), Some(sex)) => (birth, sex),
+        (None, None) => return Ok(None),
+        _ => return Err(From::from("percentiles need both --birth DATE and --sex SEX")),
+    };
+    let mut reference = GrowthReference::who();
+    for &(measure, ref path) in &opts.cdc {
+        reference = reference.with_cdc_path(path, measure)?;
     }
+    Ok(Some(GrowthChart{reference, birth, sex}))
+}
+
+fn growth_rows(opts: &Options, chart: Option<&GrowthChart>) -> Result<Vec<babystats::GrowthRow>, Box<dyn Error>> {
+    let measurements: Vec<_> = read_events(opts)?.into_iter().filter_map(|e| match e {
+        Event::Measure(me) => Some(me),
+        _ => None,
+    }).collect();
+    Ok(babystats::growth_table(&measurements, chart))
 }
 
-struct Iter<'a, R: 'a> {
-    iter: csv::DeserializeRecordsIter<'a, R, RawRecord>
+// Warns about measurements too old for the reference to give percentiles
+// for, which happens after 24 months without --cdc.
+fn warn_beyond_reference(chart: &GrowthChart, rows: &[babystats::GrowthRow]) {
+    for &measure in &[GrowthMeasure::Weight, GrowthMeasure::Height, GrowthMeasure::HeadCirc] {
+        let max = chart.reference.max_age_months(chart.sex, measure).unwrap_or(0.0);
+        let beyond = rows.iter().filter(|r| r.age_months.is_some_and(|age| age > max)).any(|r| match measure {
+            GrowthMeasure::Weight => r.weight.is_some(),
+            GrowthMeasure::Height => r.height.is_some(),
+            GrowthMeasure::HeadCirc => r.head_circ.is_some(),
+        });
+        if beyond {
+            eprintln!("no {} reference past {} months, so later measurements have no percentiles; \
+                       add a CDC table with --cdc {}=FILE", measure, max, measure);
+        }
+    }
 }
 
-impl<'a, R : io::Read> Iterator for Iter<'a, R> {
-    type Item = Result<Record, Box<Error>>;
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.iter.next() {
-            None => None,
-            Some(Err(e)) => Some(Err(Box::new(e))),
-            Some(Ok(raw_record)) => Some(raw_record.into_record()),
+fn growth(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let units = opts.units();
+    let chart = growth_chart(opts)?;
+    let rows = growth_rows(opts, chart.as_ref())?;
+    if let Some(ref chart) = chart {
+        warn_beyond_reference(chart, &rows);
+    }
+    let centile = |p: Option<f64>, z: Option<f64>| match (p, z) {
+        (Some(p), Some(z)) => format!(" (p{:.0}, z {:+.2})", p, z),
+        _ => String::new(),
+    };
+    emit(opts.format, &rows, |r| {
+        let mut parts = Vec::new();
+        if let Some(weight) = r.weight {
+            parts.push(format!("weight {}{}", weight.display(units), centile(r.weight_percentile, r.weight_z)));
         }
+        if let Some(gain) = r.gain_per_day {
+            parts.push(format!("{:+.0} g/day", gain.kg() * 1000.0));
+        }
+        if let Some(height) = r.height {
+            parts.push(format!("height {}{}", height.display(units), centile(r.height_percentile, r.height_z)));
+        }
+        if let Some(head_circ) = r.head_circ {
+            parts.push(format!("head {}{}", head_circ.display(units), centile(r.head_circ_percentile, r.head_circ_z)));
+        }
+        let mut label = vec![r.time.date_naive().to_string()];
+        label.extend(opts.profile.iter().map(|p| p.week_label(r.time.date_naive())));
+        label.extend(r.age_months.map(|a| format!("{:.1} months", a)));
+        format!("{}: {}", label.join(", "), parts.join(", "))
+    })
+}
+
+fn crossings(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let chart = growth_chart(opts)?.ok_or("crossings needs --birth DATE and --sex SEX, or --profile FILE")?;
+    let rows = growth_rows(opts, Some(&chart))?;
+    warn_beyond_reference(&chart, &rows);
+    let crossings = babystats::percentile_crossings(&rows);
+    emit(opts.format, &crossings, |c| {
+        format!("{} to {}: {} went from p{:.0} to p{:.0}, {} {} percentile line{}", c.from.date_naive(),
+                c.to.date_naive(), c.measure, c.from_percentile, c.to_percentile,
+                if c.lines > 0 { "up" } else { "down" }, c.lines.abs(), if c.lines.abs() == 1 { "" } else { "s" })
+    })
+}
+
+fn summary(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let config = SummaryConfig{
+        night: opts.night,
+        session_gap: opts.gap,
+        feeding: opts.feeding,
+    };
+    let days = babystats::daily_summaries(&read_events(opts)?, &config);
+    if opts.format == Format::Text {
+        print!("{}", SummaryTable{summaries: &days, units: opts.units(), profile: opts.profile.as_ref()});
+        return Ok(());
+    }
+    emit(opts.format, &days, |_| unreachable!())
+}
+
+fn trend(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let metric = opts.metric.ok_or("trend needs --metric METRIC")?;
+    let series = metric.daily_series(&read_events(opts)?, opts.units());
+    let points = babystats::rolling(&series, &opts.rolling);
+    let unit = metric.unit(opts.units());
+    let num = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{:.1}", v));
+    emit(opts.format, &points, |p| {
+        format!("{}: {} {} (mean {}, median {}, min {}, max {}, sd {} over {} days)", p.date, num(p.value), unit,
+                num(p.mean), num(p.median), num(p.min), num(p.max), num(p.std_dev), p.count)
+    })
+}
+
+fn periods(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let metric = opts.metric.ok_or("periods needs --metric METRIC")?;
+    let series = metric.daily_series(&read_events(opts)?, opts.units());
+    let stats = babystats::aggregate(&series, opts.period.as_ref().unwrap_or(&Period::Week));
+    let unit = metric.unit(opts.units());
+    emit(opts.format, &stats, |p| {
+        let delta = match (p.delta, p.delta_percent) {
+            (Some(d), Some(pct)) => format!(" ({:+.1}, {:+.0}%)", d, pct),
+            (Some(d), None) => format!(" ({:+.1})", d),
+            _ => String::new(),
+        };
+        format!("{}: {:.1} {}/day{}, total {:.1} over {} days  {}", p.label, p.mean, unit, delta, p.total,
+                p.days, babystats::sparkline(&p.daily))
+    })?;
+    if opts.format == Format::Text && stats.len() > 1 {
+        let means: Vec<Option<f64>> = stats.iter().map(|p| Some(p.mean)).collect();
+        println!("trend: {}", babystats::sparkline(&means));
+    }
+    Ok(())
+}
+
+fn vaccinations(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let birth = opts.birth().ok_or("vaccinations needs --birth DATE or --profile FILE")?;
+    let schedule = match opts.schedule {
+        Some(ref path) => Schedule::from_path(path)?,
+        None => Schedule::cdc(),
+    };
+    let vaccinations: Vec<_> = read_events(opts)?.into_iter().filter_map(|e| match e {
+        Event::Vaccination(ve) => Some(ve),
+        _ => None,
+    }).collect();
+    let history = babystats::vaccination_history(&schedule, birth, &vaccinations);
+    let as_of = opts.as_of.unwrap_or_else(|| Local::now().date_naive());
+    emit(opts.format, &history.rows(as_of), |r| {
+        let status = match (r.given, r.days_late) {
+            (Some(given), _) => format!("given {}", given),
+            (None, Some(late)) => format!("overdue by {} days", late),
+            (None, None) if r.due > as_of => format!("upcoming, due in {} days", (r.due - as_of).num_days()),
+            (None, None) => "due today".to_string(),
+        };
+        format!("{} dose {} (due {}): {}", r.vaccine, r.dose, r.due, status)
+    })?;
+    for ve in &history.unscheduled {
+        eprintln!("unscheduled: {} on {}", ve.vaccine, ve.time.date_naive());
     }
+    Ok(())
 }
 
-fn run() -> Result<(), Box<Error>> {
-    let mut rdr = BabyManagerData::from_reader(io::stdin());
-    let mut records_by_date: BTreeMap<chrono::Date<Local>, Vec<Record>> = BTreeMap::new();
-    for record in &mut rdr.into_iter().map(|r| r.unwrap()) {
-        records_by_date.entry(record.time().date()).or_insert(Vec::new()).push(record.clone());
+fn compare(opts: &Options) -> Result<(), Box<dyn Error>> {
+    let metric = opts.metric.ok_or("compare needs --metric METRIC")?;
+    let mut groups = sources_by_child(read_child_sources(opts)?);
+    // Events not recorded for any child are counted for every child, as
+    // with --child.
+    let shared = groups.remove(&None).unwrap_or_default();
+    if groups.is_empty() {
+        return Err(From::from("compare needs each event's child, from a Child column or NAME=FILE"));
     }
-    let max_sleep_by_date: Vec<_> = records_by_date.into_iter().map(|(k, v)| {
-        v.into_iter().filter_map(|r| {
-            match r {
-                Record::Sleep(sr) => Some(sr),
-                _ => None,
+    let events = groups.into_iter().filter_map(|(child, mut sources)| {
+        sources.extend(shared.iter().cloned());
+        child.map(|child| (child, merge_sources(sources)))
+    }).collect();
+    let comparison = babystats::compare_children(&events, metric, opts.units());
+    let num = |v: &Option<f64>| v.map_or("-".to_string(), |v| format!("{:.1}", v));
+    match opts.format {
+        Format::Text => {
+            let unit = metric.unit(opts.units());
+            for row in &comparison.rows {
+                let values: Vec<String> = comparison.children.iter().zip(&row.values).
+                    map(|(child, v)| format!("{} {}", child, num(v))).
+                    collect();
+                println!("{}: {} ({})", row.date, values.join(", "), unit);
             }
-        }).fold(None, |acc, r| {
-            match acc {
-                None => Some(r),
-                Some(a) => if a.duration > r.duration {
-                    Some(a)
-                } else {
-                    Some(r)
-                }
+        },
+        Format::Json => {
+            serde_json::to_writer_pretty(io::stdout(), &comparison)?;
+            println!();
+        },
+        Format::Csv => {
+            let mut wtr = csv::Writer::from_writer(io::stdout());
+            wtr.write_record(Some("date".to_string()).into_iter().chain(comparison.children.iter().cloned()))?;
+            for row in &comparison.rows {
+                let values = row.values.iter().map(|v| v.map_or(String::new(), |v| v.to_string()));
+                wtr.write_record(Some(row.date.to_string()).into_iter().chain(values))?;
             }
-        })
-    }).filter_map(|v| v).collect();
-    for sr in max_sleep_by_date {
-        println!("{:?}: {}", sr.start.date(), duration_str(sr.duration))
+            wtr.flush()?;
+        },
     }
     Ok(())
 }
 
-fn duration_str(mut d: chrono::Duration) -> String {
-    let hours = d.num_hours();
-    d = d - chrono::Duration::hours(hours);
-    let minutes = d.num_minutes();
-    d = d - chrono::Duration::minutes(minutes);
-    let seconds = d.num_seconds();
-    format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+fn merge(opts: &Options) -> Result<(), Box<dyn Error>> {
+    // Each child's events are merged apart, so that twins fed at the same
+    // time aren't taken for duplicates.
+    let groups = match opts.child {
+        Some(ref child) => Some((Some(child.clone()), read_sources(opts)?)).into_iter().collect(),
+        None => sources_by_child(read_child_sources(opts)?),
+    };
+    let mut events = Vec::new();
+    let mut reports = Vec::new();
+    for (child, sources) in groups {
+        let report = babystats::merge(sources, opts.window);
+        events.extend(report.events.iter().map(|event| ChildEvent{child: child.clone(), event: event.clone()}));
+        reports.push((child, report));
+    }
+    events.sort_by_key(|ce| ce.event.time());
+    // The events are written with their child when one is named, so that it
+    // isn't lost.
+    let children = events.iter().any(|ce| ce.child.is_some());
+    if opts.format == Format::Json {
+        if children {
+            serde_json::to_writer_pretty(io::stdout(), &events)?;
+        } else {
+            let events: Vec<&Event> = events.iter().map(|ce| &ce.event).collect();
+            serde_json::to_writer_pretty(io::stdout(), &events)?;
+        }
+        println!();
+    } else {
+        let mut wtr = BabyManagerWriter::from_writer(io::stdout()).with_units(opts.units());
+        if children {
+            wtr = wtr.with_child_column();
+        }
+        for ce in &events {
+            wtr.write_child_event(ce)?;
+        }
+        wtr.flush()?;
+    }
+    for (child, report) in &reports {
+        if reports.len() > 1 {
+            eprintln!("{}:", child.as_ref().map_or("no child", |c| c.as_str()));
+        }
+        eprint!("{}", report);
+    }
+    Ok(())
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let opts = Options::parse(env::args().skip(1))?;
+    match opts.command.as_str() {
+        "sleep" => sleep(&opts),
+        "wakeups" => wakeups(&opts),
+        "pumping" => pumping(&opts),
+        "pump-times" => pump_times(&opts),
+        "pump-check" => pump_check(&opts),
+        "feeding" => feeding(&opts),
+        "feed-times" => feed_times(&opts),
+        "clusters" => clusters(&opts),
+        "intake" => intake(&opts),
+        "sides" => sides(&opts),
+        "next-side" => next_side(&opts),
+        "diapers" => diapers(&opts),
+        "diaper-alerts" => diaper_alerts(&opts),
+        "diaper-log" => diaper_log(&opts),
+        "stools" => stools(&opts),
+        "growth" => growth(&opts),
+        "crossings" => crossings(&opts),
+        "summary" => summary(&opts),
+        "trend" => trend(&opts),
+        "periods" => periods(&opts),
+        "vaccinations" => vaccinations(&opts),
+        "compare" => compare(&opts),
+        "merge" => merge(&opts),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        },
+        cmd => Err(From::from(format!("unknown command {}\n\n{}", cmd, USAGE))),
+    }
 }
 
 fn main() {
@@ -0,0 +1,111 @@
+use std::fmt;
+
+use chrono;
+
+use super::{Event,FeedingEvent};
+
+/// A pair of events of the same type that start close together but differ
+/// in duration or quantity. Both are kept in the merged stream.
+#[derive(Debug,Clone)]
+pub struct NearDuplicate {
+    pub first: Event,
+    pub second: Event,
+}
+
+/// The result of merging several exports into one stream.
+#[derive(Debug,Clone,Default)]
+pub struct MergeReport {
+    /// The merged events, ordered by time.
+    pub events: Vec<Event>,
+    /// The number of events read from each source, in the order given.
+    pub events_per_source: Vec<usize>,
+    /// The number of events dropped for exactly matching an event from
+    /// another source.
+    pub exact_duplicates: usize,
+    /// Events that look like the same activity recorded twice.
+    pub near_duplicates: Vec<NearDuplicate>,
+}
+
+impl fmt::Display for MergeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let read: usize = self.events_per_source.iter().sum();
+        writeln!(f, "merged {} events from {} sources into {}",
+                 read, self.events_per_source.len(), self.events.len())?;
+        writeln!(f, "dropped {} exact duplicates", self.exact_duplicates)?;
+        writeln!(f, "flagged {} near-duplicates", self.near_duplicates.len())?;
+        for nd in &self.near_duplicates {
+            writeln!(f, "  {:?}", nd.first)?;
+            writeln!(f, "  {:?}", nd.second)?;
+        }
+        Ok(())
+    }
+}
+
+/// Merges the events from several sources into one time ordered stream.
+///
+/// Events that are exactly equal to an earlier event from another source are
+/// dropped. Repeats within one source, such as two identical bottles, are
+/// kept. Events of the same type that start within `window` of each other
+/// but differ in duration or quantity are kept and reported as
+/// near-duplicates for review.
+pub fn merge<I>(sources: I, window: chrono::Duration) -> MergeReport
+    where I: IntoIterator<Item=Vec<Event>> {
+    let mut report = MergeReport::default();
+    let mut all = Vec::new();
+    for (source, events) in sources.into_iter().enumerate() {
+        report.events_per_source.push(events.len());
+        all.extend(events.into_iter().map(|event| (source, event)));
+    }
+    all.sort_by_key(|(_, e)| e.time());
+
+    // The source of each kept event.
+    let mut kept_sources = Vec::new();
+    for (source, event) in all {
+        let mut duplicate = false;
+        let mut near = None;
+        for (kept, &kept_source) in report.events.iter().zip(&kept_sources).rev() {
+            if event.time().signed_duration_since(kept.time()) > window {
+                break;
+            }
+            if *kept == event && kept_source != source {
+                duplicate = true;
+                break;
+            }
+            if near.is_none() && differ_in_amount(kept, &event) {
+                near = Some(kept.clone());
+            }
+        }
+        if duplicate {
+            report.exact_duplicates += 1;
+            continue;
+        }
+        if let Some(first) = near {
+            report.near_duplicates.push(NearDuplicate{
+                first,
+                second: event.clone(),
+            });
+        }
+        report.events.push(event);
+        kept_sources.push(source);
+    }
+    report
+}
+
+// Whether two events are of the same type but differ in duration or
+// quantity.
+fn differ_in_amount(a: &Event, b: &Event) -> bool {
+    match (a, b) {
+        (Event::Sleep(a), Event::Sleep(b)) => a.duration != b.duration,
+        (Event::Feeding(FeedingEvent::Bottle(a)), Event::Feeding(FeedingEvent::Bottle(b))) => a.volume != b.volume,
+        (Event::Feeding(FeedingEvent::LeftBreast(a)), Event::Feeding(FeedingEvent::LeftBreast(b))) |
+        (Event::Feeding(FeedingEvent::RightBreast(a)), Event::Feeding(FeedingEvent::RightBreast(b))) => {
+            a.duration != b.duration
+        },
+        (Event::Pumping(a), Event::Pumping(b)) => a.volume != b.volume,
+        (Event::TummyTime(a), Event::TummyTime(b)) => a.duration != b.duration,
+        (Event::Measure(a), Event::Measure(b)) => {
+            a.weight != b.weight || a.height != b.height || a.head_circ != b.head_circ
+        },
+        _ => false,
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use chrono::{self,NaiveDate};
+
+use super::{diapers_by_date,feeding_by_date,intake_by_date,FeedingConfig,pumping_by_date,sleep_by_date,sleep_sessions,Event,UnitSystem,
+            Volume,DEFAULT_SESSION_GAP_MINUTES};
+
+/// A quantity that can be measured for each day.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Metric {
+    /// The longest sleep session, in hours.
+    LongestSleep,
+    /// The time spent asleep, in hours.
+    TotalSleep,
+    /// The number of feeds, breast and bottle combined.
+    Feeds,
+    /// The mean time between the start of one feed and the next, in hours.
+    FeedInterval,
+    /// The volume of milk given by bottle.
+    BottleVolume,
+    /// The volume of formula given by bottle.
+    FormulaVolume,
+    /// The percentage of breast milk in bottles of breast milk and formula.
+    BreastMilkShare,
+    /// The number of diaper changes.
+    Diapers,
+    WetDiapers,
+    DirtyDiapers,
+    /// The volume of milk pumped.
+    Pumped,
+    /// The number of pumping sessions.
+    PumpingSessions,
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Metric, String> {
+        match s {
+            "longest-sleep" => Ok(Metric::LongestSleep),
+            "total-sleep" => Ok(Metric::TotalSleep),
+            "feeds" => Ok(Metric::Feeds),
+            "feed-interval" => Ok(Metric::FeedInterval),
+            "bottle-volume" => Ok(Metric::BottleVolume),
+            "formula-volume" => Ok(Metric::FormulaVolume),
+            "breast-milk-share" => Ok(Metric::BreastMilkShare),
+            "diapers" => Ok(Metric::Diapers),
+            "wet-diapers" => Ok(Metric::WetDiapers),
+            "dirty-diapers" => Ok(Metric::DirtyDiapers),
+            "pumped" => Ok(Metric::Pumped),
+            "pumping-sessions" => Ok(Metric::PumpingSessions),
+            _ => Err(format!("unknown metric {}; expecting longest-sleep, total-sleep, feeds, feed-interval, \
+                              bottle-volume, formula-volume, breast-milk-share, diapers, wet-diapers, dirty-diapers, pumped \
+                              or pumping-sessions", s)),
+        }
+    }
+}
+
+impl Metric {
+    /// The unit the metric's values are in.
+    pub fn unit(&self, units: UnitSystem) -> &'static str {
+        match (*self, units) {
+            (Metric::LongestSleep, _) | (Metric::TotalSleep, _) | (Metric::FeedInterval, _) => "hours",
+            (Metric::Feeds, _) => "feeds",
+            (Metric::PumpingSessions, _) => "sessions",
+            (Metric::BreastMilkShare, _) => "%",
+            (Metric::Diapers, _) | (Metric::WetDiapers, _) | (Metric::DirtyDiapers, _) => "diapers",
+            (Metric::BottleVolume, UnitSystem::Imperial) | (Metric::FormulaVolume, UnitSystem::Imperial) |
+            (Metric::Pumped, UnitSystem::Imperial) => "oz",
+            (Metric::BottleVolume, UnitSystem::Metric) | (Metric::FormulaVolume, UnitSystem::Metric) |
+            (Metric::Pumped, UnitSystem::Metric) => "ml",
+        }
+    }
+
+    /// The metric's value on each day it has one, with volumes in `units`.
+    pub fn daily_series(&self, events: &[Event], units: UnitSystem) -> BTreeMap<NaiveDate, f64> {
+        let volume = |v: Volume| match units {
+            UnitSystem::Imperial => v.oz() as f64,
+            UnitSystem::Metric => v.ml() as f64,
+        };
+        let hours = |d: chrono::Duration| d.num_minutes() as f64 / 60.0;
+        match *self {
+            Metric::LongestSleep | Metric::TotalSleep => {
+                let sleeps: Vec<_> = events.iter().filter_map(|e| match *e {
+                    Event::Sleep(ref se) => Some(se.clone()),
+                    _ => None,
+                }).collect();
+                let sessions = sleep_sessions(&sleeps, chrono::Duration::minutes(DEFAULT_SESSION_GAP_MINUTES));
+                sleep_by_date(&sessions, 1).into_iter().map(|d| {
+                    (d.date, hours(if *self == Metric::LongestSleep { d.longest } else { d.total }))
+                }).collect()
+            },
+            Metric::Feeds | Metric::FeedInterval | Metric::BottleVolume => {
+                let feeds: Vec<_> = events.iter().filter_map(|e| match *e {
+                    Event::Feeding(ref fe) => Some(fe.clone()),
+                    _ => None,
+                }).collect();
+                feeding_by_date(&feeds, &FeedingConfig::default()).into_iter().filter_map(|d| {
+                    match *self {
+                        Metric::Feeds => Some((d.date, d.feeds as f64)),
+                        Metric::FeedInterval => d.mean_interval.map(|i| (d.date, hours(i))),
+                        _ => Some((d.date, volume(d.bottle_volume))),
+                    }
+                }).collect()
+            },
+            Metric::FormulaVolume | Metric::BreastMilkShare => {
+                let feeds: Vec<_> = events.iter().filter_map(|e| match *e {
+                    Event::Feeding(ref fe) => Some(fe.clone()),
+                    _ => None,
+                }).collect();
+                intake_by_date(&feeds).into_iter().filter_map(|d| {
+                    match *self {
+                        Metric::FormulaVolume => Some((d.date, volume(d.formula))),
+                        _ => d.breast_milk_share.map(|s| (d.date, s * 100.0)),
+                    }
+                }).collect()
+            },
+            Metric::Diapers | Metric::WetDiapers | Metric::DirtyDiapers => {
+                let diapers: Vec<_> = events.iter().filter_map(|e| match *e {
+                    Event::Diaper(ref de) => Some(de.clone()),
+                    _ => None,
+                }).collect();
+                diapers_by_date(&diapers).into_iter().map(|d| {
+                    let count = match *self {
+                        Metric::WetDiapers => d.wet,
+                        Metric::DirtyDiapers => d.dirty,
+                        _ => d.changes,
+                    };
+                    (d.date, count as f64)
+                }).collect()
+            },
+            Metric::Pumped | Metric::PumpingSessions => {
+                let sessions: Vec<_> = events.iter().filter_map(|e| match *e {
+                    Event::Pumping(ref pe) => Some(pe.clone()),
+                    _ => None,
+                }).collect();
+                pumping_by_date(&sessions).into_iter().map(|d| {
+                    (d.date, if *self == Metric::Pumped { volume(d.volume) } else { d.sessions as f64 })
+                }).collect()
+            },
+        }
+    }
+}
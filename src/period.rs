@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use chrono::{Datelike,NaiveDate};
+
+/// A way of dividing dates into periods.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Period {
+    /// ISO 8601 weeks, starting on Monday.
+    Week,
+    /// Calendar months.
+    Month,
+    /// Blocks of the given number of days, starting from the first date.
+    Days(u32),
+    /// Periods starting on each of the given dates and running until the
+    /// next one, or the last date. Dates before the first are ignored.
+    Custom(Vec<NaiveDate>),
+}
+
+impl FromStr for Period {
+    type Err = String;
+
+    /// Parses `week`, `month`, a number of days such as `14d`, or a comma
+    /// separated list of start dates such as `2018-03-01,2018-03-20`.
+    fn from_str(s: &str) -> Result<Period, String> {
+        let err = || format!("unknown period {}; expecting week, month, a number of days such as 14d, \
+                              or comma separated start dates", s);
+        match s {
+            "week" => Ok(Period::Week),
+            "month" => Ok(Period::Month),
+            _ if s.ends_with('d') => match s[..s.len() - 1].parse() {
+                Ok(days) if days > 0 => Ok(Period::Days(days)),
+                _ => Err(err()),
+            },
+            _ => {
+                let mut starts = Vec::new();
+                for date in s.split(',') {
+                    starts.push(NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").map_err(|_| err())?);
+                }
+                starts.sort();
+                Ok(Period::Custom(starts))
+            },
+        }
+    }
+}
+
+impl Period {
+    // The first and last date of the period containing `date`, given the
+    // first and last dates in the data.
+    pub(crate) fn bounds(&self, date: NaiveDate, first: NaiveDate, last: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+        match *self {
+            Period::Week => {
+                let start = date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64);
+                Some((start, start + chrono::Duration::days(6)))
+            },
+            Period::Month => {
+                let start = date.with_day(1).unwrap();
+                let next = if start.month() == 12 {
+                    NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(start.year(), start.month() + 1, 1)
+                };
+                Some((start, next.unwrap().pred_opt().unwrap()))
+            },
+            Period::Days(days) => {
+                let days = days as i64;
+                let start = first + chrono::Duration::days(date.signed_duration_since(first).num_days() / days * days);
+                Some((start, start + chrono::Duration::days(days - 1)))
+            },
+            Period::Custom(ref starts) => {
+                let i = starts.iter().rposition(|&s| s <= date)?;
+                let end = match starts.get(i + 1) {
+                    Some(next) => next.pred_opt().unwrap(),
+                    None => last,
+                };
+                Some((starts[i], end))
+            },
+        }
+    }
+
+    pub(crate) fn label(&self, start: NaiveDate, end: NaiveDate) -> String {
+        match *self {
+            Period::Week => {
+                let week = start.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            },
+            Period::Month => start.format("%Y-%m").to_string(),
+            Period::Days(_) | Period::Custom(_) => format!("{}..{}", start, end),
+        }
+    }
+}
+
+/// A metric aggregated over one period.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct PeriodStats {
+    /// e.g. `2018-W09`, `2018-03` or `2018-03-01..2018-03-14`.
+    pub label: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    /// The number of days in the period with a value.
+    pub days: usize,
+    pub total: f64,
+    /// The mean over the days with a value.
+    pub mean: f64,
+    /// The change in `mean` from the previous period.
+    pub delta: Option<f64>,
+    /// `delta` as a percentage of the previous period's mean.
+    pub delta_percent: Option<f64>,
+    /// The value on each day of the period that falls within the data,
+    /// `None` for days without one.
+    #[serde(skip)]
+    pub daily: Vec<Option<f64>>,
+}
+
+/// Aggregates a daily metric by period, in date order.
+pub fn aggregate(series: &BTreeMap<NaiveDate, f64>, period: &Period) -> Vec<PeriodStats> {
+    let (first, last) = match (series.keys().next(), series.keys().next_back()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return Vec::new(),
+    };
+    let mut groups: BTreeMap<(NaiveDate, NaiveDate), Vec<f64>> = BTreeMap::new();
+    for (&date, &value) in series {
+        if let Some(bounds) = period.bounds(date, first, last) {
+            groups.entry(bounds).or_default().push(value);
+        }
+    }
+
+    let mut stats: Vec<PeriodStats> = Vec::new();
+    for ((start, end), values) in groups {
+        let total: f64 = values.iter().sum();
+        let mean = total / values.len() as f64;
+        let prev = stats.last().map(|p| p.mean);
+        let days = start.max(first).iter_days().take_while(|&d| d <= end.min(last));
+        stats.push(PeriodStats{
+            label: period.label(start, end),
+            start,
+            end,
+            days: values.len(),
+            total,
+            mean,
+            delta: prev.map(|p| mean - p),
+            delta_percent: prev.and_then(|p| if p == 0.0 { None } else { Some((mean - p) / p * 100.0) }),
+            daily: days.map(|d| series.get(&d).cloned()).collect(),
+        });
+    }
+    stats
+}
+
+/// Renders values as a line of block characters scaled from the smallest to
+/// the largest value, with a space for missing values.
+pub fn sparkline(values: &[Option<f64>]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let present = values.iter().filter_map(|&v| v);
+    let min = present.clone().fold(f64::INFINITY, f64::min);
+    let max = present.fold(f64::NEG_INFINITY, f64::max);
+    values.iter().map(|&v| match v {
+        None => ' ',
+        Some(_) if max <= min => BARS[BARS.len() / 2],
+        Some(v) => BARS[((v - min) / (max - min) * (BARS.len() - 1) as f64).round() as usize],
+    }).collect()
+}
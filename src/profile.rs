@@ -0,0 +1,154 @@
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use chrono::{self,NaiveDate};
+use serde_json;
+use toml;
+
+use super::{GrowthChart,GrowthReference,Sex};
+
+/// The gestational age of a baby born at term, in weeks.
+pub const TERM_WEEKS: f64 = 40.0;
+
+/// What's known about a baby beyond what the export records.
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct BabyProfile {
+    pub name: String,
+    pub birth: NaiveDate,
+    #[serde(default)]
+    pub sex: Option<Sex>,
+    /// The gestational age at birth in weeks, e.g. `34.5`.
+    #[serde(default)]
+    pub gestational_weeks: Option<f64>,
+}
+
+impl BabyProfile {
+    /// Whether the baby was born before term, so that corrected ages apply.
+    pub fn is_preterm(&self) -> bool {
+        self.gestational_weeks.is_some_and(|w| w < TERM_WEEKS)
+    }
+
+    /// The date the baby was due, or the birth date when born at term or
+    /// the gestational age isn't known.
+    pub fn due(&self) -> NaiveDate {
+        match self.gestational_weeks {
+            Some(weeks) if weeks < TERM_WEEKS => {
+                self.birth + chrono::Duration::days(((TERM_WEEKS - weeks) * 7.0).round() as i64)
+            },
+            _ => self.birth,
+        }
+    }
+
+    /// The age in days on a date.
+    pub fn age_days(&self, date: NaiveDate) -> i64 {
+        date.signed_duration_since(self.birth).num_days()
+    }
+
+    /// The age in days on a date, counted from the due date for babies born
+    /// preterm. Negative before the due date.
+    pub fn corrected_age_days(&self, date: NaiveDate) -> i64 {
+        date.signed_duration_since(self.due()).num_days()
+    }
+
+    /// Labels a date by the week of life it falls in, `week 1` being the
+    /// first seven days, with the corrected week too for preterm babies,
+    /// e.g. `week 7 (corrected 2)`.
+    pub fn week_label(&self, date: NaiveDate) -> String {
+        let week = |days: i64| days.div_euclid(7) + 1;
+        if self.is_preterm() {
+            format!("week {} (corrected {})", week(self.age_days(date)), week(self.corrected_age_days(date)))
+        } else {
+            format!("week {}", week(self.age_days(date)))
+        }
+    }
+
+    /// A growth chart for the baby, using corrected age for babies born
+    /// preterm. `None` when the sex isn't known.
+    pub fn growth_chart(&self, reference: GrowthReference) -> Option<GrowthChart> {
+        Some(GrowthChart{
+            reference,
+            birth: self.due(),
+            sex: self.sex?,
+        })
+    }
+}
+
+/// Errors reading profiles.
+#[derive(Debug)]
+pub enum ProfileError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProfileError::Io(ref e) => write!(f, "reading profile: {}", e),
+            ProfileError::Toml(ref e) => write!(f, "parsing profile: {}", e),
+            ProfileError::Json(ref e) => write!(f, "parsing profile: {}", e),
+        }
+    }
+}
+
+impl error::Error for ProfileError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            ProfileError::Io(ref e) => Some(e),
+            ProfileError::Toml(ref e) => Some(e),
+            ProfileError::Json(ref e) => Some(e),
+        }
+    }
+}
+
+// A profile file holds either one profile, or a list of them under `child`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ProfileFile {
+    Many { child: Vec<BabyProfile> },
+    One(BabyProfile),
+}
+
+impl ProfileFile {
+    fn into_profiles(self) -> Vec<BabyProfile> {
+        match self {
+            ProfileFile::Many { child } => child,
+            ProfileFile::One(profile) => vec![profile],
+        }
+    }
+}
+
+/// Reads profiles from TOML such as
+///
+/// ```toml
+/// [[child]]
+/// name = "Ada"
+/// birth = "2018-03-01"
+/// sex = "female"
+/// gestational_weeks = 35
+/// ```
+///
+/// or a single profile without the `[[child]]` header.
+pub fn profiles_from_toml(s: &str) -> Result<Vec<BabyProfile>, ProfileError> {
+    toml::from_str::<ProfileFile>(s).map(ProfileFile::into_profiles).map_err(ProfileError::Toml)
+}
+
+/// Reads profiles from JSON, either one profile object or an object with a
+/// `child` list of them.
+pub fn profiles_from_json(s: &str) -> Result<Vec<BabyProfile>, ProfileError> {
+    serde_json::from_str::<ProfileFile>(s).map(ProfileFile::into_profiles).map_err(ProfileError::Json)
+}
+
+/// Reads profiles from a `.json` file, or else from TOML.
+pub fn profiles_from_path<P: AsRef<Path>>(path: P) -> Result<Vec<BabyProfile>, ProfileError> {
+    let path = path.as_ref();
+    let s = fs::read_to_string(path).map_err(ProfileError::Io)?;
+    if path.extension().is_some_and(|e| e == "json") {
+        profiles_from_json(&s)
+    } else {
+        profiles_from_toml(&s)
+    }
+}
@@ -0,0 +1,95 @@
+use chrono::{NaiveDate,Timelike};
+
+use super::{group_by_date,PumpingEvent,Timestamp,Volume};
+
+/// Pumping output on one date.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct PumpingDay {
+    pub date: NaiveDate,
+    pub sessions: usize,
+    pub volume: Volume,
+    /// Output from the left side, for sessions that recorded the split.
+    pub left: Volume,
+    /// Output from the right side, for sessions that recorded the split.
+    pub right: Volume,
+    pub mean_per_session: Volume,
+}
+
+// The mean volume of sessions, which must not be empty.
+fn mean(sessions: &[&PumpingEvent]) -> Volume {
+    Volume::from_ml(sessions.iter().map(|pe| pe.volume.ml()).sum::<f32>() / sessions.len() as f32)
+}
+
+/// Totals pumping sessions by the date they started on.
+pub fn pumping_by_date(sessions: &[PumpingEvent]) -> Vec<PumpingDay> {
+    group_by_date(sessions, |pe| pe.start.date_naive()).into_iter().map(|(date, sessions)| {
+        PumpingDay{
+            date,
+            sessions: sessions.len(),
+            volume: sessions.iter().map(|pe| pe.volume).sum(),
+            left: sessions.iter().filter_map(|pe| pe.left).sum(),
+            right: sessions.iter().filter_map(|pe| pe.right).sum(),
+            mean_per_session: mean(&sessions),
+        }
+    }).collect()
+}
+
+/// The output of sessions starting within a range of hours.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct TimeOfDayYield {
+    /// The first hour of the range, from 0 to 23.
+    pub start_hour: u32,
+    /// The hour after the end of the range, from 1 to 24.
+    pub end_hour: u32,
+    pub sessions: usize,
+    /// The mean output per session, `None` when there were no sessions.
+    pub mean: Option<Volume>,
+}
+
+/// Averages output per session by the time of day the session started, in
+/// ranges of `hours` hours.
+pub fn yield_by_time_of_day(sessions: &[PumpingEvent], hours: u32) -> Vec<TimeOfDayYield> {
+    let hours = hours.clamp(1, 24);
+    (0..24).step_by(hours as usize).map(|start_hour| {
+        let end_hour = (start_hour + hours).min(24);
+        let matching: Vec<&PumpingEvent> = sessions.iter().
+            filter(|pe| pe.start.hour() >= start_hour && pe.start.hour() < end_hour).
+            collect();
+        TimeOfDayYield{
+            start_hour,
+            end_hour,
+            sessions: matching.len(),
+            mean: if matching.is_empty() { None } else { Some(mean(&matching)) },
+        }
+    }).collect()
+}
+
+/// A session whose per-side split doesn't add up to its total.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct PumpingMismatch {
+    pub start: Timestamp,
+    pub volume: Volume,
+    pub left: Volume,
+    pub right: Volume,
+    /// `left + right - volume`.
+    pub difference: Volume,
+}
+
+/// Finds sessions recording both sides where `left + right` differs from
+/// the total by more than `tolerance`.
+pub fn pumping_mismatches(sessions: &[PumpingEvent], tolerance: Volume) -> Vec<PumpingMismatch> {
+    sessions.iter().filter_map(|pe| {
+        let (left, right) = (pe.left?, pe.right?);
+        let difference = left.ml() + right.ml() - pe.volume.ml();
+        if difference.abs() <= tolerance.ml() {
+            return None;
+        }
+        Some(PumpingMismatch{
+            start: pe.start,
+            volume: pe.volume,
+            left,
+            right,
+            difference: Volume::from_ml(difference),
+        })
+    }).collect()
+}
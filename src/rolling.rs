@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+
+/// Where the window sits relative to the day being computed.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum Alignment {
+    /// The day and the days before it.
+    #[default]
+    Trailing,
+    /// The day and the days either side of it.
+    Centered,
+}
+
+impl FromStr for Alignment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Alignment, String> {
+        match s {
+            "trailing" => Ok(Alignment::Trailing),
+            "centered" => Ok(Alignment::Centered),
+            _ => Err(format!("unknown alignment {}; expecting trailing or centered", s)),
+        }
+    }
+}
+
+/// How days without a value are treated.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum MissingDays {
+    /// Leave them out, computing statistics over the days that have values.
+    #[default]
+    Skip,
+    /// Count them as zero, e.g. for counts where nothing recorded means none.
+    Zero,
+    /// Don't compute statistics for windows containing them.
+    Gap,
+}
+
+impl FromStr for MissingDays {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<MissingDays, String> {
+        match s {
+            "skip" => Ok(MissingDays::Skip),
+            "zero" => Ok(MissingDays::Zero),
+            "gap" => Ok(MissingDays::Gap),
+            _ => Err(format!("unknown missing day policy {}; expecting skip, zero or gap", s)),
+        }
+    }
+}
+
+/// Describes the window rolling statistics are computed over.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct RollingConfig {
+    /// The number of calendar days in the window.
+    pub days: usize,
+    pub alignment: Alignment,
+    pub missing: MissingDays,
+}
+
+impl Default for RollingConfig {
+    fn default() -> RollingConfig {
+        RollingConfig{
+            days: 7,
+            alignment: Alignment::Trailing,
+            missing: MissingDays::Skip,
+        }
+    }
+}
+
+/// Statistics over the window around one day.
+///
+/// The statistics are `None` when the window holds no values, or when it
+/// holds a missing day under `MissingDays::Gap`.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct RollingPoint {
+    pub date: NaiveDate,
+    /// The day's own value, `None` when it has none and missing days
+    /// aren't counted as zero.
+    pub value: Option<f64>,
+    /// The number of values the statistics were computed over.
+    pub count: usize,
+    pub mean: Option<f64>,
+    pub median: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// The population standard deviation.
+    pub std_dev: Option<f64>,
+}
+
+/// Computes rolling statistics for every calendar day from the first to the
+/// last day in `series`, including days missing from it.
+pub fn rolling(series: &BTreeMap<NaiveDate, f64>, config: &RollingConfig) -> Vec<RollingPoint> {
+    let (first, last) = match (series.keys().next(), series.keys().next_back()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return Vec::new(),
+    };
+    let days = config.days.max(1) as i64;
+    let (before, after) = match config.alignment {
+        Alignment::Trailing => (days - 1, 0),
+        Alignment::Centered => (days / 2, (days - 1) / 2),
+    };
+    let value = |date: NaiveDate| -> Option<f64> {
+        match series.get(&date) {
+            Some(&v) => Some(v),
+            None if config.missing == MissingDays::Zero => Some(0.0),
+            None => None,
+        }
+    };
+
+    first.iter_days().take_while(|&d| d <= last).map(|date| {
+        let mut values = Vec::new();
+        let mut gap = false;
+        for offset in -before..after + 1 {
+            let day = date + chrono::Duration::days(offset);
+            if day < first || day > last {
+                continue;
+            }
+            match value(day) {
+                Some(v) => values.push(v),
+                None => gap = true,
+            }
+        }
+        if gap && config.missing == MissingDays::Gap {
+            values.clear();
+        }
+        let mut point = RollingPoint{
+            date,
+            value: value(date),
+            count: values.len(),
+            mean: None,
+            median: None,
+            min: None,
+            max: None,
+            std_dev: None,
+        };
+        if !values.is_empty() {
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let n = values.len() as f64;
+            let mean = values.iter().sum::<f64>() / n;
+            let mid = values.len() / 2;
+            point.mean = Some(mean);
+            point.median = Some(if values.len() % 2 == 0 {
+                (values[mid - 1] + values[mid]) / 2.0
+            } else {
+                values[mid]
+            });
+            point.min = Some(values[0]);
+            point.max = Some(values[values.len() - 1]);
+            point.std_dev = Some((values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt());
+        }
+        point
+    }).collect()
+}
@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+
+use chrono::{self,NaiveDate};
+
+use super::{duration_serialize,group_by_date,rolling,RollingConfig,SleepEvent,Timestamp};
+
+/// The default longest brief wake within one sleep session, in minutes.
+pub const DEFAULT_SESSION_GAP_MINUTES: i64 = 15;
+
+/// A continuous stretch of sleep, recorded as one or more `SleepEvent`s split
+/// by brief wakes.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct SleepSession {
+    /// The sleeps making up the session, in time order.
+    pub segments: Vec<SleepEvent>,
+}
+
+impl SleepSession {
+    pub fn start(&self) -> Timestamp {
+        self.segments[0].start
+    }
+
+    pub fn end(&self) -> Timestamp {
+        self.segments[self.segments.len() - 1].end.unwrap()
+    }
+
+    /// The time from falling asleep to finally waking, including brief wakes.
+    pub fn span(&self) -> chrono::Duration {
+        self.end().signed_duration_since(self.start())
+    }
+
+    /// The time spent asleep, excluding brief wakes.
+    pub fn sleep(&self) -> chrono::Duration {
+        self.segments.iter().fold(chrono::Duration::zero(), |a, se| a + se.duration)
+    }
+
+    /// The number of brief wakes within the session.
+    pub fn wakes(&self) -> usize {
+        self.segments.len() - 1
+    }
+}
+
+/// Stitches completed sleeps into sessions, joining sleeps that start within
+/// `max_gap` of the previous one ending.
+pub fn sleep_sessions(sleeps: &[SleepEvent], max_gap: chrono::Duration) -> Vec<SleepSession> {
+    let mut sleeps: Vec<&SleepEvent> = sleeps.iter().filter(|se| se.end.is_some()).collect();
+    sleeps.sort_by_key(|se| se.start);
+    let mut sessions: Vec<SleepSession> = Vec::new();
+    for se in sleeps {
+        if let Some(session) = sessions.last_mut() {
+            if se.start.signed_duration_since(session.end()) <= max_gap {
+                session.segments.push(se.clone());
+                continue;
+            }
+        }
+        sessions.push(SleepSession{segments: vec![se.clone()]});
+    }
+    sessions
+}
+
+/// Sleep statistics for the sessions that ended on one date.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct SleepDay {
+    pub date: NaiveDate,
+    pub sessions: usize,
+    /// The time spent asleep, excluding brief wakes.
+    #[serde(serialize_with = "duration_serialize")]
+    pub total: chrono::Duration,
+    /// The longest session, including brief wakes.
+    #[serde(serialize_with = "duration_serialize")]
+    pub longest: chrono::Duration,
+    /// The mean of `longest` over this and the preceding days, up to the
+    /// window passed to `sleep_by_date`.
+    #[serde(serialize_with = "duration_serialize")]
+    pub longest_average: chrono::Duration,
+}
+
+/// Summarizes sleep sessions by the date they ended on, averaging the
+/// longest session over a trailing window of `window` calendar days.
+pub fn sleep_by_date(sessions: &[SleepSession], window: usize) -> Vec<SleepDay> {
+    let mut days: Vec<SleepDay> = group_by_date(sessions, |s| s.end().date_naive()).into_iter().map(|(date, sessions)| {
+        let longest = sessions.iter().map(|s| s.span()).max().unwrap();
+        SleepDay{
+            date,
+            sessions: sessions.len(),
+            total: sessions.iter().fold(chrono::Duration::zero(), |a, s| a + s.sleep()),
+            longest,
+            longest_average: longest,
+        }
+    }).collect();
+
+    let series: BTreeMap<NaiveDate, f64> = days.iter().map(|d| (d.date, d.longest.num_minutes() as f64)).collect();
+    let config = RollingConfig{
+        days: window,
+        ..Default::default()
+    };
+    let averages: BTreeMap<NaiveDate, f64> = rolling(&series, &config).into_iter().
+        filter_map(|p| p.mean.map(|m| (p.date, m))).
+        collect();
+    for day in &mut days {
+        day.longest_average = chrono::Duration::seconds((averages[&day.date] * 60.0).round() as i64);
+    }
+    days
+}
@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+
+use super::{group_by_date,list_serialize,DiaperEvent,Period,Timestamp};
+
+/// The color of a stool.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Serialize,Deserialize)]
+pub enum StoolColor {
+    Yellow,
+    Green,
+    Brown,
+    /// Black, including meconium.
+    Black,
+    Red,
+    /// White, pale or clay colored.
+    White,
+}
+
+impl StoolColor {
+    fn from_word(word: &str) -> Option<StoolColor> {
+        match word {
+            "yellow" | "mustard" | "orange" => Some(StoolColor::Yellow),
+            "green" => Some(StoolColor::Green),
+            "brown" | "tan" => Some(StoolColor::Brown),
+            "black" | "meconium" | "tarry" => Some(StoolColor::Black),
+            "red" | "blood" | "bloody" => Some(StoolColor::Red),
+            "white" | "pale" | "clay" | "grey" | "gray" => Some(StoolColor::White),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for StoolColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StoolColor::Yellow => write!(f, "yellow"),
+            StoolColor::Green => write!(f, "green"),
+            StoolColor::Brown => write!(f, "brown"),
+            StoolColor::Black => write!(f, "black"),
+            StoolColor::Red => write!(f, "red"),
+            StoolColor::White => write!(f, "white"),
+        }
+    }
+}
+
+/// The consistency of a stool, from the thinnest to the firmest.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Serialize,Deserialize)]
+pub enum StoolConsistency {
+    Watery,
+    /// Runny or loose.
+    Runny,
+    Seedy,
+    /// Soft or pasty.
+    Soft,
+    Formed,
+    /// Hard, or pellets.
+    Hard,
+}
+
+impl StoolConsistency {
+    fn from_word(word: &str) -> Option<StoolConsistency> {
+        match word {
+            "watery" => Some(StoolConsistency::Watery),
+            "runny" | "loose" | "diarrhea" | "diarrhoea" => Some(StoolConsistency::Runny),
+            "seedy" => Some(StoolConsistency::Seedy),
+            "soft" | "pasty" | "mushy" => Some(StoolConsistency::Soft),
+            "formed" => Some(StoolConsistency::Formed),
+            "hard" | "pellet" | "pellets" => Some(StoolConsistency::Hard),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for StoolConsistency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StoolConsistency::Watery => write!(f, "watery"),
+            StoolConsistency::Runny => write!(f, "runny"),
+            StoolConsistency::Seedy => write!(f, "seedy"),
+            StoolConsistency::Soft => write!(f, "soft"),
+            StoolConsistency::Formed => write!(f, "formed"),
+            StoolConsistency::Hard => write!(f, "hard"),
+        }
+    }
+}
+
+/// The size of a stool.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Serialize,Deserialize)]
+pub enum StoolAmount {
+    Small,
+    Medium,
+    /// Large, including blowouts.
+    Large,
+}
+
+impl StoolAmount {
+    fn from_word(word: &str) -> Option<StoolAmount> {
+        match word {
+            "small" | "little" | "smear" => Some(StoolAmount::Small),
+            "medium" | "moderate" => Some(StoolAmount::Medium),
+            "large" | "big" | "huge" | "blowout" => Some(StoolAmount::Large),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for StoolAmount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StoolAmount::Small => write!(f, "small"),
+            StoolAmount::Medium => write!(f, "medium"),
+            StoolAmount::Large => write!(f, "large"),
+        }
+    }
+}
+
+fn is_cream(word: &str) -> bool {
+    matches!(word, "cream" | "ointment" | "zinc" | "vaseline" | "balm")
+}
+
+/// What was noted about a diaper beyond whether it was wet or dirty.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default,Serialize,Deserialize)]
+pub struct DiaperDetail {
+    pub color: Option<StoolColor>,
+    pub consistency: Option<StoolConsistency>,
+    pub amount: Option<StoolAmount>,
+    /// Whether a diaper rash was noted.
+    pub rash: bool,
+    /// Whether cream or ointment was applied.
+    pub cream: bool,
+}
+
+impl DiaperDetail {
+    /// Reads keywords such as `green`, `seedy`, `blowout`, `rash` or
+    /// `cream` from each text in turn. The first keyword of each kind is
+    /// used, and keywords straight after `no` or `not` are ignored.
+    pub fn from_texts(texts: &[&str]) -> DiaperDetail {
+        let mut detail = DiaperDetail::default();
+        for text in texts {
+            let text = text.to_lowercase();
+            let words: Vec<&str> = text.split(|c: char| !c.is_alphabetic()).filter(|w| !w.is_empty()).collect();
+            for (i, &word) in words.iter().enumerate() {
+                if i > 0 && (words[i - 1] == "no" || words[i - 1] == "not") {
+                    continue;
+                }
+                detail.color = detail.color.or_else(|| StoolColor::from_word(word));
+                detail.consistency = detail.consistency.or_else(|| StoolConsistency::from_word(word));
+                detail.amount = detail.amount.or_else(|| StoolAmount::from_word(word));
+                detail.rash |= word == "rash";
+                detail.cream |= is_cream(word);
+            }
+        }
+        detail
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == DiaperDetail::default()
+    }
+
+    /// The details that `other` doesn't already give, e.g. those not read
+    /// from a diaper's note.
+    pub(crate) fn without(&self, other: &DiaperDetail) -> DiaperDetail {
+        DiaperDetail{
+            color: self.color.filter(|&c| other.color != Some(c)),
+            consistency: self.consistency.filter(|&c| other.consistency != Some(c)),
+            amount: self.amount.filter(|&a| other.amount != Some(a)),
+            rash: self.rash && !other.rash,
+            cream: self.cream && !other.cream,
+        }
+    }
+}
+
+impl fmt::Display for DiaperDetail {
+    /// Writes the details as keywords, e.g. `green, seedy, rash`, that
+    /// `from_texts` reads back.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut words: Vec<String> = Vec::new();
+        words.extend(self.color.map(|c| c.to_string()));
+        words.extend(self.consistency.map(|c| c.to_string()));
+        words.extend(self.amount.map(|a| a.to_string()));
+        if self.rash {
+            words.push("rash".to_string());
+        }
+        if self.cream {
+            words.push("cream".to_string());
+        }
+        write!(f, "{}", words.join(", "))
+    }
+}
+
+/// Selects diapers that were wet or dirty or had all of some details.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub struct DiaperQuery {
+    pub wet: bool,
+    pub dirty: bool,
+    pub detail: DiaperDetail,
+}
+
+impl FromStr for DiaperQuery {
+    type Err = String;
+
+    /// Parses keywords separated by commas or spaces, e.g. `dirty,green` or
+    /// `rash`.
+    fn from_str(s: &str) -> Result<DiaperQuery, String> {
+        let mut query = DiaperQuery::default();
+        for word in s.split(|c: char| c == ',' || c.is_whitespace()).filter(|w| !w.is_empty()) {
+            let detail = DiaperDetail::from_texts(&[word]);
+            match word.to_lowercase().as_str() {
+                "wet" => query.wet = true,
+                "dirty" => query.dirty = true,
+                _ if detail.is_empty() => {
+                    return Err(format!("unknown diaper detail {}; expecting wet, dirty, a stool color, \
+                                        consistency or amount, rash or cream", word));
+                },
+                _ => {
+                    query.detail.color = query.detail.color.or(detail.color);
+                    query.detail.consistency = query.detail.consistency.or(detail.consistency);
+                    query.detail.amount = query.detail.amount.or(detail.amount);
+                    query.detail.rash |= detail.rash;
+                    query.detail.cream |= detail.cream;
+                },
+            }
+        }
+        Ok(query)
+    }
+}
+
+impl DiaperQuery {
+    pub fn matches(&self, de: &DiaperEvent) -> bool {
+        let d = &de.detail;
+        (!self.wet || de.pee) &&
+            (!self.dirty || de.poo) &&
+            self.detail.color.is_none_or(|c| d.color == Some(c)) &&
+            self.detail.consistency.is_none_or(|c| d.consistency == Some(c)) &&
+            self.detail.amount.is_none_or(|a| d.amount == Some(a)) &&
+            (!self.detail.rash || d.rash) &&
+            (!self.detail.cream || d.cream)
+    }
+}
+
+/// A diaper change with its details as separate fields, so the log can be
+/// written as CSV rows.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct DiaperLogRow {
+    pub time: Timestamp,
+    pub wet: bool,
+    pub dirty: bool,
+    /// The stool color, or empty when not noted.
+    pub color: String,
+    pub consistency: String,
+    pub amount: String,
+    pub rash: bool,
+    pub cream: bool,
+    pub note: String,
+}
+
+impl From<&DiaperEvent> for DiaperLogRow {
+    fn from(de: &DiaperEvent) -> DiaperLogRow {
+        let d = &de.detail;
+        DiaperLogRow{
+            time: de.time,
+            wet: de.pee,
+            dirty: de.poo,
+            color: d.color.map(|c| c.to_string()).unwrap_or_default(),
+            consistency: d.consistency.map(|c| c.to_string()).unwrap_or_default(),
+            amount: d.amount.map(|a| a.to_string()).unwrap_or_default(),
+            rash: d.rash,
+            cream: d.cream,
+            note: de.note.clone(),
+        }
+    }
+}
+
+/// The diaper changes matching a query, in order of time.
+pub fn diaper_log(diapers: &[DiaperEvent], query: &DiaperQuery) -> Vec<DiaperLogRow> {
+    let mut rows: Vec<DiaperLogRow> = diapers.iter().filter(|de| query.matches(de)).map(DiaperLogRow::from).collect();
+    rows.sort_by_key(|r| r.time);
+    rows
+}
+
+/// Diaper details over a day or a period.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct StoolSummary {
+    /// The date, or the label of the period, e.g. `2018-W09`.
+    pub label: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub changes: usize,
+    pub dirty: usize,
+    /// The number of dirty diapers of each color, e.g. `yellow 4; green 1`.
+    #[serde(serialize_with = "list_serialize")]
+    pub colors: BTreeMap<StoolColor, usize>,
+    #[serde(serialize_with = "list_serialize")]
+    pub consistencies: BTreeMap<StoolConsistency, usize>,
+    #[serde(serialize_with = "list_serialize")]
+    pub amounts: BTreeMap<StoolAmount, usize>,
+    /// The number of changes noting a rash.
+    pub rashes: usize,
+    /// The number of changes where cream was applied.
+    pub creams: usize,
+}
+
+/// Summarizes diaper details by date, or by period when one is given.
+pub fn stool_summary(diapers: &[DiaperEvent], period: Option<&Period>) -> Vec<StoolSummary> {
+    let dates = group_by_date(diapers, |de| de.time.date_naive());
+    let (first, last) = match (dates.keys().next(), dates.keys().next_back()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return Vec::new(),
+    };
+    let mut groups: BTreeMap<(NaiveDate, NaiveDate), Vec<&DiaperEvent>> = BTreeMap::new();
+    for (date, diapers) in dates {
+        let bounds = match period {
+            Some(period) => match period.bounds(date, first, last) {
+                Some(bounds) => bounds,
+                None => continue,
+            },
+            None => (date, date),
+        };
+        groups.entry(bounds).or_default().extend(diapers);
+    }
+
+    groups.into_iter().map(|((start, end), diapers)| {
+        let mut summary = StoolSummary{
+            label: period.map_or_else(|| start.to_string(), |p| p.label(start, end)),
+            start,
+            end,
+            changes: diapers.len(),
+            dirty: 0,
+            colors: BTreeMap::new(),
+            consistencies: BTreeMap::new(),
+            amounts: BTreeMap::new(),
+            rashes: 0,
+            creams: 0,
+        };
+        for de in diapers {
+            let d = &de.detail;
+            if de.poo {
+                summary.dirty += 1;
+                if let Some(color) = d.color {
+                    *summary.colors.entry(color).or_insert(0) += 1;
+                }
+                if let Some(consistency) = d.consistency {
+                    *summary.consistencies.entry(consistency).or_insert(0) += 1;
+                }
+                if let Some(amount) = d.amount {
+                    *summary.amounts.entry(amount).or_insert(0) += 1;
+                }
+            }
+            summary.rashes += d.rash as usize;
+            summary.creams += d.cream as usize;
+        }
+        summary
+    }).collect()
+}
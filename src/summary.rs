@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono::{self,NaiveDate};
+
+use super::{duration_serialize,feeds,list_serialize,nights,sleep_sessions,str_from_duration,BabyProfile,Event,
+            FeedingConfig,FeedingEvent,Milk,NightConfig,UnitSystem,Volume,DEFAULT_SESSION_GAP_MINUTES};
+
+/// Settings for splitting sleep into day and night, and grouping feeds.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct SummaryConfig {
+    pub night: NightConfig,
+    /// The longest brief wake within one sleep session.
+    pub session_gap: chrono::Duration,
+    pub feeding: FeedingConfig,
+}
+
+impl Default for SummaryConfig {
+    fn default() -> SummaryConfig {
+        SummaryConfig{
+            night: NightConfig::default(),
+            session_gap: chrono::Duration::minutes(DEFAULT_SESSION_GAP_MINUTES),
+            feeding: FeedingConfig::default(),
+        }
+    }
+}
+
+/// A summary of everything recorded on one date.
+///
+/// Night sleep is counted on the date of the evening the night started.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct DailySummary {
+    pub date: NaiveDate,
+    #[serde(serialize_with = "duration_serialize")]
+    pub night_sleep: chrono::Duration,
+    #[serde(serialize_with = "duration_serialize")]
+    pub day_sleep: chrono::Duration,
+    /// The longest sleep session, including brief wakes.
+    #[serde(serialize_with = "duration_serialize")]
+    pub longest_stretch: chrono::Duration,
+    /// The number of feeds, breast and bottle combined, counting a switch of
+    /// sides as one feed.
+    pub feeds: usize,
+    pub breast_milk_volume: Volume,
+    pub formula_volume: Volume,
+    pub mixed_volume: Volume,
+    pub donor_volume: Volume,
+    /// Bottles of any other or an unknown type of milk.
+    pub other_milk_volume: Volume,
+    #[serde(serialize_with = "duration_serialize")]
+    pub left_breast: chrono::Duration,
+    #[serde(serialize_with = "duration_serialize")]
+    pub right_breast: chrono::Duration,
+    pub wet_diapers: usize,
+    pub dirty_diapers: usize,
+    pub pumped_volume: Volume,
+    #[serde(serialize_with = "duration_serialize")]
+    pub tummy_time: chrono::Duration,
+    /// The time and note of each meal.
+    #[serde(serialize_with = "list_serialize")]
+    pub meals: Vec<String>,
+    /// The time and text of each note.
+    #[serde(serialize_with = "list_serialize")]
+    pub notes: Vec<String>,
+}
+
+impl DailySummary {
+    fn new(date: NaiveDate) -> DailySummary {
+        DailySummary{
+            date,
+            night_sleep: chrono::Duration::zero(),
+            day_sleep: chrono::Duration::zero(),
+            longest_stretch: chrono::Duration::zero(),
+            feeds: 0,
+            breast_milk_volume: Volume::default(),
+            formula_volume: Volume::default(),
+            mixed_volume: Volume::default(),
+            donor_volume: Volume::default(),
+            other_milk_volume: Volume::default(),
+            left_breast: chrono::Duration::zero(),
+            right_breast: chrono::Duration::zero(),
+            wet_diapers: 0,
+            dirty_diapers: 0,
+            pumped_volume: Volume::default(),
+            tummy_time: chrono::Duration::zero(),
+            meals: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn total_sleep(&self) -> chrono::Duration {
+        self.night_sleep + self.day_sleep
+    }
+
+    pub fn bottle_volume(&self) -> Volume {
+        self.breast_milk_volume + self.formula_volume + self.mixed_volume + self.donor_volume + self.other_milk_volume
+    }
+}
+
+/// Summarizes events by date.
+pub fn daily_summaries(events: &[Event], config: &SummaryConfig) -> Vec<DailySummary> {
+    let mut days: BTreeMap<NaiveDate, DailySummary> = BTreeMap::new();
+    let sleeps: Vec<_> = events.iter().filter_map(|e| match *e {
+        Event::Sleep(ref se) => Some(se.clone()),
+        _ => None,
+    }).collect();
+
+    // Each sleep is counted once, as part of a night or else as a day sleep.
+    let mut night_dates = BTreeMap::new();
+    for night in nights(&sleeps, &config.night) {
+        for se in sleeps.iter().filter(|se| se.start >= night.bedtime && se.start < night.wake_time) {
+            night_dates.insert(se.start, night.date);
+        }
+    }
+    for session in sleep_sessions(&sleeps, config.session_gap) {
+        let date = night_dates.get(&session.start()).cloned().unwrap_or_else(|| session.start().date_naive());
+        let day = days.entry(date).or_insert_with(|| DailySummary::new(date));
+        day.longest_stretch = day.longest_stretch.max(session.span());
+    }
+    for se in sleeps.iter().filter(|se| se.end.is_some()) {
+        match night_dates.get(&se.start) {
+            Some(&date) => days.entry(date).or_insert_with(|| DailySummary::new(date)).night_sleep += se.duration,
+            None => {
+                let date = se.start.date_naive();
+                days.entry(date).or_insert_with(|| DailySummary::new(date)).day_sleep += se.duration;
+            },
+        }
+    }
+
+    let feeding: Vec<_> = events.iter().filter_map(|e| match *e {
+        Event::Feeding(ref fe) => Some(fe.clone()),
+        _ => None,
+    }).collect();
+    for feed in feeds(&feeding, &config.feeding) {
+        let date = feed.start().date_naive();
+        days.entry(date).or_insert_with(|| DailySummary::new(date)).feeds += 1;
+    }
+
+    for event in events {
+        if let Event::Sleep(_) = *event {
+            continue;
+        }
+        let date = event.time().date_naive();
+        let day = days.entry(date).or_insert_with(|| DailySummary::new(date));
+        let time = event.time().format("%H:%M");
+        match *event {
+            Event::Feeding(ref fe) => match *fe {
+                FeedingEvent::Bottle(ref be) => match be.milk {
+                    Milk::BreastMilk => day.breast_milk_volume = day.breast_milk_volume + be.volume,
+                    Milk::Formula => day.formula_volume = day.formula_volume + be.volume,
+                    Milk::Mixed => day.mixed_volume = day.mixed_volume + be.volume,
+                    Milk::Donor => day.donor_volume = day.donor_volume + be.volume,
+                    Milk::Other(_) | Milk::Unknown => day.other_milk_volume = day.other_milk_volume + be.volume,
+                },
+                FeedingEvent::LeftBreast(ref be) => day.left_breast += be.duration,
+                FeedingEvent::RightBreast(ref be) => day.right_breast += be.duration,
+            },
+            Event::Diaper(ref de) => {
+                day.wet_diapers += de.pee as usize;
+                day.dirty_diapers += de.poo as usize;
+            },
+            Event::Pumping(ref pe) => day.pumped_volume = day.pumped_volume + pe.volume,
+            Event::TummyTime(ref te) => day.tummy_time += te.duration,
+            Event::Meal(ref me) => day.meals.push(format!("{} {}", time, me.note).trim_end().to_string()),
+            Event::Note(ref ne) => day.notes.push(format!("{} {}", time, ne.note).trim_end().to_string()),
+            _ => {},
+        }
+    }
+    days.into_values().collect()
+}
+
+/// Renders summaries as a table with one row per date, for printing.
+pub struct SummaryTable<'a> {
+    pub summaries: &'a [DailySummary],
+    pub units: UnitSystem,
+    /// Adds a column labelling each date by age, e.g. `week 7`.
+    pub profile: Option<&'a BabyProfile>,
+}
+
+impl<'a> fmt::Display for SummaryTable<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut header = vec!["Date", "Night", "Day", "Longest", "Feeds", "Breast milk", "Formula", "Mixed", "Donor",
+                      "Other milk",
+                      "Left", "Right", "Wet", "Dirty", "Pumped", "Tummy", "Meals", "Notes"];
+        let units = self.units;
+        let mut rows: Vec<Vec<String>> = self.summaries.iter().map(|d| vec![
+            d.date.to_string(),
+            str_from_duration(&d.night_sleep),
+            str_from_duration(&d.day_sleep),
+            str_from_duration(&d.longest_stretch),
+            d.feeds.to_string(),
+            d.breast_milk_volume.display(units).to_string(),
+            d.formula_volume.display(units).to_string(),
+            d.mixed_volume.display(units).to_string(),
+            d.donor_volume.display(units).to_string(),
+            d.other_milk_volume.display(units).to_string(),
+            str_from_duration(&d.left_breast),
+            str_from_duration(&d.right_breast),
+            d.wet_diapers.to_string(),
+            d.dirty_diapers.to_string(),
+            d.pumped_volume.display(units).to_string(),
+            str_from_duration(&d.tummy_time),
+            d.meals.len().to_string(),
+            d.notes.len().to_string(),
+        ]).collect();
+        if let Some(profile) = self.profile {
+            header.insert(1, "Age");
+            for (row, d) in rows.iter_mut().zip(self.summaries) {
+                row.insert(1, profile.week_label(d.date));
+            }
+        }
+        let widths: Vec<usize> = header.iter().enumerate().map(|(i, h)| {
+            rows.iter().map(|r| r[i].len()).chain(Some(h.len())).max().unwrap()
+        }).collect();
+
+        let header: Vec<String> = header.iter().map(|h| h.to_string()).collect();
+        for row in Some(&header).into_iter().chain(rows.iter()) {
+            let cells: Vec<String> = row.iter().zip(&widths).enumerate().map(|(i, (cell, &width))| {
+                if i == 0 {
+                    format!("{:<1$}", cell, width)
+                } else {
+                    format!("{:>1$}", cell, width)
+                }
+            }).collect();
+            writeln!(f, "{}", cells.join("  "))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono;
+use chrono::{FixedOffset,LocalResult,NaiveDateTime,Offset,TimeZone};
+use chrono::offset::Local;
+use chrono_tz;
+
+/// A point in time along with the UTC offset it was recorded in.
+pub type Timestamp = chrono::DateTime<FixedOffset>;
+
+/// The timezone Baby Manager's wall-clock timestamps are interpreted in.
+#[derive(Debug,Clone,Copy,PartialEq,Default)]
+pub enum TimeZoneSpec {
+    /// The timezone of the machine doing the parsing.
+    #[default]
+    Local,
+    /// A fixed offset from UTC, with no daylight saving time.
+    Fixed(FixedOffset),
+    /// A named IANA timezone such as `Europe/London`.
+    Named(chrono_tz::Tz),
+}
+
+impl fmt::Display for TimeZoneSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TimeZoneSpec::Local => write!(f, "local"),
+            TimeZoneSpec::Fixed(ref off) => write!(f, "{}", off),
+            TimeZoneSpec::Named(ref tz) => write!(f, "{}", tz.name()),
+        }
+    }
+}
+
+impl FromStr for TimeZoneSpec {
+    type Err = String;
+
+    /// Parses `local`, `UTC`, a fixed offset like `+05:30` or `-0800`, or an
+    /// IANA timezone name.
+    fn from_str(s: &str) -> Result<TimeZoneSpec, String> {
+        if s.eq_ignore_ascii_case("local") {
+            return Ok(TimeZoneSpec::Local);
+        }
+        if s.eq_ignore_ascii_case("utc") || s == "Z" {
+            return Ok(TimeZoneSpec::Fixed(FixedOffset::east_opt(0).unwrap()));
+        }
+        if s.starts_with('+') || s.starts_with('-') {
+            return s.parse::<FixedOffset>().
+                map(TimeZoneSpec::Fixed).
+                map_err(|e| format!("invalid UTC offset {}: {}", s, e));
+        }
+        s.parse::<chrono_tz::Tz>().
+            map(TimeZoneSpec::Named).
+            map_err(|_| format!("unknown timezone {}", s))
+    }
+}
+
+/// How wall-clock times that are ambiguous or skipped by a daylight saving
+/// transition are resolved.
+///
+/// When clocks go back, a time such as 01:30 occurs twice; `Earliest` picks
+/// the first occurrence and `Latest` the second. When clocks go forward, a
+/// time such as 02:30 never occurs; it's interpreted using the offsets in
+/// effect on either side of the transition, and `Earliest` and `Latest`
+/// pick the earlier or later of the two resulting instants. `Error` rejects
+/// both cases.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum DstPolicy {
+    #[default]
+    Earliest,
+    Latest,
+    Error,
+}
+
+impl FromStr for DstPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<DstPolicy, String> {
+        match s {
+            "earliest" => Ok(DstPolicy::Earliest),
+            "latest" => Ok(DstPolicy::Latest),
+            "error" => Ok(DstPolicy::Error),
+            _ => Err(format!("unknown DST policy {}; expecting earliest, latest or error", s)),
+        }
+    }
+}
+
+impl TimeZoneSpec {
+    /// Resolves a wall-clock time in this timezone to a `Timestamp`, or
+    /// `None` if `policy` rejects it.
+    pub fn resolve(&self, dt: &NaiveDateTime, policy: DstPolicy) -> Option<Timestamp> {
+        match *self {
+            TimeZoneSpec::Local => resolve_in(&Local, dt, policy),
+            TimeZoneSpec::Fixed(ref off) => resolve_in(off, dt, policy),
+            TimeZoneSpec::Named(ref tz) => resolve_in(tz, dt, policy),
+        }
+    }
+}
+
+fn resolve_in<Tz: TimeZone>(tz: &Tz, dt: &NaiveDateTime, policy: DstPolicy) -> Option<Timestamp> {
+    let (earliest, latest) = match tz.from_local_datetime(dt) {
+        LocalResult::Single(t) => return Some(t.fixed_offset()),
+        LocalResult::Ambiguous(a, b) => (a.fixed_offset(), b.fixed_offset()),
+        LocalResult::None => {
+            // Transitions are at most a couple of hours long, so the offsets
+            // a few hours either side are the ones before and after the gap.
+            let window = chrono::Duration::hours(3);
+            let before = tz.offset_from_local_datetime(&(*dt - window)).earliest()?.fix();
+            let after = tz.offset_from_local_datetime(&(*dt + window)).latest()?.fix();
+            let a = before.from_local_datetime(dt).single()?;
+            let b = after.from_local_datetime(dt).single()?;
+            if a < b { (a, b) } else { (b, a) }
+        },
+    };
+    match policy {
+        DstPolicy::Earliest => Some(earliest),
+        DstPolicy::Latest => Some(latest),
+        DstPolicy::Error => None,
+    }
+}
@@ -0,0 +1,219 @@
+use std::fmt;
+
+use regex::Regex;
+
+use super::UnitSystem;
+
+pub const ML_PER_OZ: f32 = 29.574;
+pub const KG_PER_LB: f32 = 0.453_592_37;
+pub const OZ_PER_LB: f32 = 16.0;
+pub const CM_PER_IN: f32 = 2.54;
+
+/// A volume of milk, stored and serialized in millilitres.
+#[derive(Debug,Clone,Copy,PartialEq,PartialOrd,Default,Serialize,Deserialize)]
+#[serde(transparent)]
+pub struct Volume {
+    ml: f32,
+}
+
+impl Volume {
+    pub fn from_ml(ml: f32) -> Volume {
+        Volume{ml}
+    }
+
+    pub fn from_oz(oz: f32) -> Volume {
+        Volume{ml: oz * ML_PER_OZ}
+    }
+
+    pub fn ml(&self) -> f32 {
+        self.ml
+    }
+
+    pub fn oz(&self) -> f32 {
+        self.ml / ML_PER_OZ
+    }
+
+    /// Renders the volume as e.g. `"4.0 oz"` or `"118 ml"`.
+    pub fn display(&self, units: UnitSystem) -> Display {
+        match units {
+            UnitSystem::Imperial => Display::one(self.oz(), 1, "oz"),
+            UnitSystem::Metric => Display::one(self.ml, 0, "ml"),
+        }
+    }
+
+    /// Parses volumes such as `"4 oz"`, `"4.5oz"` or `"120 ml"`. A number
+    /// without a unit is taken to be in `default` units.
+    pub fn parse(s: &str, default: UnitSystem) -> Option<Volume> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"^(\d+(?:\.\d+)?)\s*(oz|ml)?$").unwrap();
+        }
+        let caps = RE.captures(s.trim())?;
+        let value: f32 = caps.get(1).unwrap().as_str().parse().ok()?;
+        match caps.get(2).map(|m| m.as_str()) {
+            Some("oz") => Some(Volume::from_oz(value)),
+            Some("ml") => Some(Volume::from_ml(value)),
+            _ => match default {
+                UnitSystem::Imperial => Some(Volume::from_oz(value)),
+                UnitSystem::Metric => Some(Volume::from_ml(value)),
+            },
+        }
+    }
+}
+
+impl ::std::ops::Add for Volume {
+    type Output = Volume;
+    fn add(self, other: Volume) -> Volume {
+        Volume{ml: self.ml + other.ml}
+    }
+}
+
+impl ::std::iter::Sum for Volume {
+    fn sum<I: Iterator<Item=Volume>>(iter: I) -> Volume {
+        iter.fold(Volume::default(), |a, b| a + b)
+    }
+}
+
+/// A body weight, stored and serialized in kilograms.
+#[derive(Debug,Clone,Copy,PartialEq,PartialOrd,Default,Serialize,Deserialize)]
+#[serde(transparent)]
+pub struct Mass {
+    kg: f32,
+}
+
+impl Mass {
+    pub fn from_kg(kg: f32) -> Mass {
+        Mass{kg}
+    }
+
+    pub fn from_lb(lb: f32) -> Mass {
+        Mass{kg: lb * KG_PER_LB}
+    }
+
+    pub fn from_lb_oz(lb: f32, oz: f32) -> Mass {
+        Mass::from_lb(lb + oz / OZ_PER_LB)
+    }
+
+    pub fn kg(&self) -> f32 {
+        self.kg
+    }
+
+    pub fn lb(&self) -> f32 {
+        self.kg / KG_PER_LB
+    }
+
+    /// Renders the mass as e.g. `"7 lb 8.0 oz"` or `"3.40 kg"`.
+    pub fn display(&self, units: UnitSystem) -> Display {
+        match units {
+            UnitSystem::Imperial => {
+                let total_oz = (self.lb() * OZ_PER_LB * 10.0).round() / 10.0;
+                let lb = (total_oz / OZ_PER_LB).floor();
+                Display::two(lb, "lb", total_oz - lb * OZ_PER_LB, "oz")
+            },
+            UnitSystem::Metric => Display::one(self.kg, 2, "kg"),
+        }
+    }
+
+    /// Parses masses such as `"7.5 lb"`, `"7 lb 8 oz"`, `"3.4 kg"` or
+    /// `"3400 g"`. A number without a unit is taken to be in `default` units.
+    pub fn parse(s: &str, default: UnitSystem) -> Option<Mass> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(
+                r"^(\d+(?:\.\d+)?)\s*(lb|kg|g)?(?:\s+(\d+(?:\.\d+)?)\s*oz)?$").unwrap();
+        }
+        let caps = RE.captures(s.trim())?;
+        let value: f32 = caps.get(1).unwrap().as_str().parse().ok()?;
+        let oz: Option<f32> = match caps.get(3) {
+            Some(m) => Some(m.as_str().parse().ok()?),
+            None => None,
+        };
+        match (caps.get(2).map(|m| m.as_str()), oz) {
+            (Some("lb"), oz) => Some(Mass::from_lb_oz(value, oz.unwrap_or(0.0))),
+            (_, Some(_)) => None,
+            (Some("kg"), None) => Some(Mass::from_kg(value)),
+            (Some("g"), None) => Some(Mass::from_kg(value / 1000.0)),
+            (_, None) => match default {
+                UnitSystem::Imperial => Some(Mass::from_lb(value)),
+                UnitSystem::Metric => Some(Mass::from_kg(value)),
+            },
+        }
+    }
+}
+
+/// A body length or circumference, stored and serialized in centimetres.
+#[derive(Debug,Clone,Copy,PartialEq,PartialOrd,Default,Serialize,Deserialize)]
+#[serde(transparent)]
+pub struct Length {
+    cm: f32,
+}
+
+impl Length {
+    pub fn from_cm(cm: f32) -> Length {
+        Length{cm}
+    }
+
+    pub fn from_in(inches: f32) -> Length {
+        Length{cm: inches * CM_PER_IN}
+    }
+
+    pub fn cm(&self) -> f32 {
+        self.cm
+    }
+
+    pub fn inches(&self) -> f32 {
+        self.cm / CM_PER_IN
+    }
+
+    /// Renders the length as e.g. `"21.5 in"` or `"54.6 cm"`.
+    pub fn display(&self, units: UnitSystem) -> Display {
+        match units {
+            UnitSystem::Imperial => Display::one(self.inches(), 1, "in"),
+            UnitSystem::Metric => Display::one(self.cm, 1, "cm"),
+        }
+    }
+
+    /// Parses lengths such as `"21.5 in"` or `"54 cm"`. A number without a
+    /// unit is taken to be in `default` units.
+    pub fn parse(s: &str, default: UnitSystem) -> Option<Length> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"^(\d+(?:\.\d+)?)\s*(in|cm)?$").unwrap();
+        }
+        let caps = RE.captures(s.trim())?;
+        let value: f32 = caps.get(1).unwrap().as_str().parse().ok()?;
+        match caps.get(2).map(|m| m.as_str()) {
+            Some("in") => Some(Length::from_in(value)),
+            Some("cm") => Some(Length::from_cm(value)),
+            _ => match default {
+                UnitSystem::Imperial => Some(Length::from_in(value)),
+                UnitSystem::Metric => Some(Length::from_cm(value)),
+            },
+        }
+    }
+}
+
+/// A quantity rendered in a particular unit system, returned by the
+/// `display` methods of `Volume`, `Mass` and `Length`.
+pub struct Display {
+    parts: Vec<(f32, usize, &'static str)>,
+}
+
+impl Display {
+    fn one(value: f32, precision: usize, unit: &'static str) -> Display {
+        Display{parts: vec![(value, precision, unit)]}
+    }
+
+    fn two(a: f32, a_unit: &'static str, b: f32, b_unit: &'static str) -> Display {
+        Display{parts: vec![(a, 0, a_unit), (b, 1, b_unit)]}
+    }
+}
+
+impl fmt::Display for Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, &(value, precision, unit)) in self.parts.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{:.*} {}", precision, value, unit)?;
+        }
+        Ok(())
+    }
+}
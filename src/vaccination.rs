@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use chrono::{Months,NaiveDate};
+use serde_json;
+
+use super::VaccinationEvent;
+
+/// A single dose in a vaccination schedule.
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct ScheduledDose {
+    pub vaccine: String,
+    pub dose: u32,
+    /// The recommended age in months at which the dose is given.
+    pub age_months: u32,
+}
+
+/// A vaccination schedule, e.g. a national immunization programme.
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct Schedule {
+    pub doses: Vec<ScheduledDose>,
+    /// Other names vaccinations may be recorded under, such as brand names
+    /// of combination vaccines, with the scheduled vaccines each covers.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, Vec<String>>,
+}
+
+// Lowercases a vaccine name and drops everything but letters and digits, so
+// that e.g. "Hep B" and "HepB" match.
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+impl Schedule {
+    /// The CDC's recommended schedule for birth to 18 months.
+    pub fn cdc() -> Schedule {
+        let doses: &[(&str, &[u32])] = &[
+            ("HepB", &[0, 1, 6]),
+            ("RV", &[2, 4, 6]),
+            ("DTaP", &[2, 4, 6, 15]),
+            ("Hib", &[2, 4, 6, 12]),
+            ("PCV", &[2, 4, 6, 12]),
+            ("IPV", &[2, 4, 6]),
+            ("MMR", &[12]),
+            ("Varicella", &[12]),
+            ("HepA", &[12, 18]),
+        ];
+        let aliases: &[(&str, &[&str])] = &[
+            ("Rotavirus", &["RV"]),
+            ("Pneumococcal", &["PCV"]),
+            ("Polio", &["IPV"]),
+            ("Chickenpox", &["Varicella"]),
+            ("Pediarix", &["DTaP", "HepB", "IPV"]),
+            ("Pentacel", &["DTaP", "IPV", "Hib"]),
+            ("Vaxelis", &["DTaP", "IPV", "Hib", "HepB"]),
+            ("Kinrix", &["DTaP", "IPV"]),
+            ("ProQuad", &["MMR", "Varicella"]),
+            ("MMRV", &["MMR", "Varicella"]),
+        ];
+        let mut schedule = Schedule{doses: Vec::new(), aliases: BTreeMap::new()};
+        for &(alias, vaccines) in aliases {
+            schedule.aliases.insert(alias.to_string(), vaccines.iter().map(|v| v.to_string()).collect());
+        }
+        for &(vaccine, ages) in doses {
+            for (i, &age_months) in ages.iter().enumerate() {
+                schedule.doses.push(ScheduledDose{
+                    vaccine: vaccine.to_string(),
+                    dose: i as u32 + 1,
+                    age_months,
+                });
+            }
+        }
+        schedule.doses.sort_by_key(|d| d.age_months);
+        schedule
+    }
+
+    /// Reads a schedule from JSON, in the same layout it serializes to.
+    pub fn from_reader<R: io::Read>(rdr: R) -> Result<Schedule, serde_json::Error> {
+        serde_json::from_reader(rdr)
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Schedule, serde_json::Error> {
+        let file = File::open(path).map_err(serde_json::Error::io)?;
+        Schedule::from_reader(file)
+    }
+
+    /// The scheduled vaccines a recorded vaccination covers. The name, or
+    /// each part of a combination such as "DTaP-IPV-Hib", must be a
+    /// scheduled vaccine or an alias, ignoring case, spaces and punctuation.
+    pub fn vaccines_for(&self, name: &str) -> Vec<&str> {
+        let mut names = vec![name];
+        names.extend(name.split(['-', '/', '+', '&', ',']));
+        let mut vaccines: Vec<&str> = Vec::new();
+        for name in names.into_iter().map(normalize).filter(|n| !n.is_empty()) {
+            let scheduled = self.doses.iter().map(|d| d.vaccine.as_str()).filter(|v| normalize(v) == name);
+            let aliased = self.aliases.iter().
+                filter(|&(alias, _)| normalize(alias) == name).
+                flat_map(|(_, vaccines)| vaccines.iter().map(|v| v.as_str()));
+            for vaccine in scheduled.chain(aliased) {
+                if !vaccines.contains(&vaccine) {
+                    vaccines.push(vaccine);
+                }
+            }
+        }
+        vaccines
+    }
+}
+
+/// A scheduled dose alongside the vaccination that fulfilled it, if any.
+#[derive(Debug,Clone)]
+pub struct DoseStatus {
+    pub scheduled: ScheduledDose,
+    /// The date the dose is due, given the baby's birth date.
+    pub due: NaiveDate,
+    pub given: Option<VaccinationEvent>,
+}
+
+impl DoseStatus {
+    /// How many days after the due date the dose was given, or is now
+    /// overdue by as of `today`. Negative when given early.
+    pub fn days_late(&self, today: NaiveDate) -> i64 {
+        let date = self.given.as_ref().map_or(today, |v| v.time.date_naive());
+        date.signed_duration_since(self.due).num_days()
+    }
+}
+
+/// The vaccination history compared against a schedule.
+#[derive(Debug,Clone)]
+pub struct VaccinationHistory {
+    pub doses: Vec<DoseStatus>,
+    /// Vaccinations that didn't match any scheduled dose.
+    pub unscheduled: Vec<VaccinationEvent>,
+}
+
+/// Matches vaccinations against the doses in `schedule`.
+///
+/// Vaccinations count towards the vaccines given by `Schedule::vaccines_for`,
+/// so combination vaccines such as "DTaP-IPV-Hib" count towards each of
+/// their components. Vaccinations with a dose number fill that dose; the
+/// rest fill the earliest unfilled dose.
+pub fn vaccination_history(schedule: &Schedule, birth: NaiveDate, events: &[VaccinationEvent]) -> VaccinationHistory {
+    let mut doses: Vec<DoseStatus> = schedule.doses.iter().map(|d| {
+        DoseStatus{
+            scheduled: d.clone(),
+            due: birth.checked_add_months(Months::new(d.age_months)).unwrap_or(birth),
+            given: None,
+        }
+    }).collect();
+    let mut events = events.to_vec();
+    events.sort_by_key(|e| e.time);
+
+    let mut unscheduled = Vec::new();
+    for event in events {
+        let mut matched = false;
+        for vaccine in schedule.vaccines_for(&event.vaccine) {
+            let slot = doses.iter_mut().
+                filter(|d| d.scheduled.vaccine == *vaccine && d.given.is_none()).
+                find(|d| event.dose.is_none_or(|n| n == d.scheduled.dose));
+            if let Some(slot) = slot {
+                slot.given = Some(event.clone());
+                matched = true;
+            }
+        }
+        if !matched {
+            unscheduled.push(event);
+        }
+    }
+    VaccinationHistory{
+        doses,
+        unscheduled,
+    }
+}
+
+/// A flattened view of a scheduled dose, as reported by the CLI.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct DoseRow {
+    pub vaccine: String,
+    pub dose: u32,
+    pub due: NaiveDate,
+    pub given: Option<NaiveDate>,
+    /// Days given after the due date, or overdue by when not yet given.
+    /// `None` when the dose isn't given and isn't due yet.
+    pub days_late: Option<i64>,
+}
+
+impl VaccinationHistory {
+    /// One row per scheduled dose, judging overdue doses as of `today`.
+    pub fn rows(&self, today: NaiveDate) -> Vec<DoseRow> {
+        self.doses.iter().map(|status| {
+            let late = status.days_late(today);
+            DoseRow{
+                vaccine: status.scheduled.vaccine.clone(),
+                dose: status.scheduled.dose,
+                due: status.due,
+                given: status.given.as_ref().map(|ve| ve.time.date_naive()),
+                days_late: if status.given.is_some() || late > 0 { Some(late) } else { None },
+            }
+        }).collect()
+    }
+}
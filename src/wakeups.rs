@@ -0,0 +1,165 @@
+use std::str::FromStr;
+
+use chrono::{self,NaiveDate,NaiveTime};
+
+use super::{duration_serialize,list_serialize,SleepEvent,Timestamp};
+
+/// A range of wall-clock times, which may wrap past midnight, e.g.
+/// `22:00-02:00`.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct TimeWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    pub fn new(start: NaiveTime, end: NaiveTime) -> TimeWindow {
+        TimeWindow{start, end}
+    }
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time <= self.end
+        } else {
+            time >= self.start || time <= self.end
+        }
+    }
+}
+
+impl FromStr for TimeWindow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<TimeWindow, String> {
+        let err = || format!("bad time window {}; expecting HH:MM-HH:MM", s);
+        let mut parts = s.splitn(2, '-');
+        let start = parts.next().and_then(|t| NaiveTime::parse_from_str(t.trim(), "%H:%M").ok());
+        let end = parts.next().and_then(|t| NaiveTime::parse_from_str(t.trim(), "%H:%M").ok());
+        match (start, end) {
+            (Some(start), Some(end)) => Ok(TimeWindow{start, end}),
+            _ => Err(err()),
+        }
+    }
+}
+
+/// Defines what counts as a night's sleep.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct NightConfig {
+    /// A night starts with a sleep that begins within this window.
+    pub bedtime: TimeWindow,
+    /// A night ends with a sleep that ends within this window, when one does.
+    pub wake: TimeWindow,
+    /// Sleeps separated by more than this aren't part of the same night.
+    pub max_gap: chrono::Duration,
+}
+
+impl Default for NightConfig {
+    fn default() -> NightConfig {
+        NightConfig{
+            bedtime: TimeWindow::new(NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+                                     NaiveTime::from_hms_opt(2, 0, 0).unwrap()),
+            wake: TimeWindow::new(NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+                                  NaiveTime::from_hms_opt(10, 0, 0).unwrap()),
+            max_gap: chrono::Duration::minutes(90),
+        }
+    }
+}
+
+/// One night's sleep, made up of the sleeps from bedtime to waking.
+#[derive(Debug,Clone,PartialEq,Serialize)]
+pub struct Night {
+    /// The date of the evening the night started on.
+    pub date: NaiveDate,
+    pub bedtime: Timestamp,
+    pub wake_time: Timestamp,
+    /// The number of times the baby woke before `wake_time`.
+    pub wakeups: usize,
+    /// The length of each time awake between sleeps.
+    #[serde(serialize_with = "list_serialize")]
+    pub wake_windows: Vec<chrono::Duration>,
+    #[serde(serialize_with = "duration_serialize")]
+    pub total_sleep: chrono::Duration,
+    #[serde(serialize_with = "duration_serialize")]
+    pub longest_stretch: chrono::Duration,
+}
+
+/// Finds the nights in `sleeps`.
+///
+/// A night starts with a completed sleep beginning within the bedtime
+/// window, and takes in each following sleep that starts within
+/// `max_gap` of the last one ending. It ends with the last such sleep to end
+/// within the wake window on the following morning, or else with the last
+/// sleep taken in. A night with no sleep ending in the wake window is
+/// dropped when a later sleep starts another night the same evening.
+pub fn nights(sleeps: &[SleepEvent], config: &NightConfig) -> Vec<Night> {
+    let mut sleeps: Vec<&SleepEvent> = sleeps.iter().filter(|se| se.end.is_some()).collect();
+    sleeps.sort_by_key(|se| se.start);
+
+    // The date of the evening a night starting with a sleep would start on.
+    // Bedtimes after midnight belong to the previous evening.
+    let night_date = |se: &SleepEvent| {
+        let start = se.start.naive_local();
+        if !config.bedtime.contains(start.time()) {
+            None
+        } else if start.time() >= config.bedtime.start {
+            Some(start.date())
+        } else {
+            start.date().pred_opt()
+        }
+    };
+
+    let mut nights = Vec::new();
+    let mut i = 0;
+    while i < sleeps.len() {
+        let first = sleeps[i];
+        let date = match night_date(first) {
+            Some(date) => date,
+            None => {
+                i += 1;
+                continue;
+            },
+        };
+        let morning = date.succ_opt().unwrap();
+        let latest_wake = morning.and_time(config.wake.end);
+
+        let mut last_wake = None;
+        let mut j = i;
+        while j < sleeps.len() {
+            let se = sleeps[j];
+            if j > i && se.start.signed_duration_since(sleeps[j - 1].end.unwrap()) > config.max_gap {
+                break;
+            }
+            let end = se.end.unwrap().naive_local();
+            if end > latest_wake {
+                break;
+            }
+            if end.date() == morning && config.wake.contains(end.time()) {
+                last_wake = Some(j);
+            }
+            j += 1;
+        }
+        if last_wake.is_none() && j > i && sleeps.get(j).and_then(|&se| night_date(se)) != Some(date) {
+            last_wake = Some(j - 1);
+        }
+
+        match last_wake {
+            Some(last) => {
+                let segments = &sleeps[i..last + 1];
+                let wake_windows: Vec<chrono::Duration> = segments.windows(2).map(|w| {
+                    w[1].start.signed_duration_since(w[0].end.unwrap())
+                }).collect();
+                nights.push(Night{
+                    date,
+                    bedtime: first.start,
+                    wake_time: segments[segments.len() - 1].end.unwrap(),
+                    wakeups: wake_windows.len(),
+                    wake_windows,
+                    total_sleep: segments.iter().fold(chrono::Duration::zero(), |a, se| a + se.duration),
+                    longest_stretch: segments.iter().map(|se| se.duration).max().unwrap(),
+                });
+                i = last + 1;
+            },
+            None => i += 1,
+        }
+    }
+    nights
+}
@@ -0,0 +1,243 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use csv;
+
+use super::{ChildEvent,DiaperDetail,Error,Event,FeedingEvent,Length,Mass,Milk,Timestamp,UnitSystem,Volume,
+            str_from_duration};
+
+const HEADERS: [&str; 8] = ["Type", "Start", "End", "Duration", "Extra", "Extra2", "Note", "Child"];
+
+/// Writes `Event`s in the CSV layout exported by Baby Manager, so they can
+/// be read back by `BabyManagerData` or re-imported into the app.
+pub struct BabyManagerWriter<W: io::Write> {
+    wtr: csv::Writer<W>,
+    units: UnitSystem,
+    child_column: bool,
+    headers_written: bool,
+}
+
+impl BabyManagerWriter<File> {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<BabyManagerWriter<File>, Error> {
+        Ok(BabyManagerWriter::new(csv::Writer::from_path(path)?))
+    }
+}
+
+impl<W: io::Write> BabyManagerWriter<W> {
+    pub fn from_writer(wtr: W) -> BabyManagerWriter<W> {
+        BabyManagerWriter::new(csv::Writer::from_writer(wtr))
+    }
+
+    fn new(wtr: csv::Writer<W>) -> BabyManagerWriter<W> {
+        BabyManagerWriter{
+            wtr,
+            units: UnitSystem::default(),
+            child_column: false,
+            headers_written: false,
+        }
+    }
+
+    /// Sets the units quantities and measurements are written in. Defaults
+    /// to `UnitSystem::Imperial`. Imperial quantities are written to two
+    /// decimal places, and those that wouldn't then read back exactly are
+    /// written in millilitres, kilograms or centimetres, the units they're
+    /// stored in.
+    pub fn with_units(mut self, units: UnitSystem) -> BabyManagerWriter<W> {
+        self.units = units;
+        self
+    }
+
+    /// Adds a `Child` column naming the child each event was recorded for,
+    /// as read by `BabyManagerData::child_events`.
+    pub fn with_child_column(mut self) -> BabyManagerWriter<W> {
+        self.child_column = true;
+        self
+    }
+
+    pub fn write_event(&mut self, event: &Event) -> Result<(), Error> {
+        self.write(event, None)
+    }
+
+    /// Writes an event with its child, which is only written given
+    /// `with_child_column`.
+    pub fn write_child_event(&mut self, ce: &ChildEvent) -> Result<(), Error> {
+        self.write(&ce.event, ce.child.as_deref())
+    }
+
+    fn write(&mut self, event: &Event, child: Option<&str>) -> Result<(), Error> {
+        let columns = if self.child_column { HEADERS.len() } else { HEADERS.len() - 1 };
+        if !self.headers_written {
+            self.wtr.write_record(&HEADERS[..columns])?;
+            self.headers_written = true;
+        }
+        let record = RawRecord::from_event(event, self.units);
+        let child = [child.unwrap_or("")];
+        self.wtr.write_record(record.fields().iter().chain(&child).take(columns))?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.wtr.flush().map_err(csv::Error::from)?;
+        Ok(())
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn into_inner(self) -> Result<W, Error> {
+        Ok(self.wtr.into_inner().map_err(|e| csv::Error::from(e.into_error()))?)
+    }
+}
+
+#[derive(Default)]
+struct RawRecord {
+    typ: &'static str,
+    start: String,
+    end: String,
+    duration: String,
+    extra: String,
+    extra2: String,
+    note: String,
+}
+
+impl RawRecord {
+    fn from_event(event: &Event, units: UnitSystem) -> RawRecord {
+        // Imperial quantities are only written when they read back exactly.
+        let volume = |v: &Volume| match units {
+            UnitSystem::Imperial => str_from_quantity(v, v.oz(), "oz", Volume::from_oz),
+            UnitSystem::Metric => None,
+        }.unwrap_or_else(|| format!("{} ml", v.ml()));
+        let mass = |m: &Mass| match units {
+            UnitSystem::Imperial => str_from_quantity(m, m.lb(), "lb", Mass::from_lb),
+            UnitSystem::Metric => None,
+        }.unwrap_or_else(|| format!("{} kg", m.kg()));
+        let length = |l: &Length| match units {
+            UnitSystem::Imperial => str_from_quantity(l, l.inches(), "in", Length::from_in),
+            UnitSystem::Metric => None,
+        }.unwrap_or_else(|| format!("{} cm", l.cm()));
+        match *event {
+            Event::Sleep(ref e) => RawRecord{
+                typ: "Sleep",
+                start: str_from_datetime(&e.start),
+                end: e.end.as_ref().map(str_from_datetime).unwrap_or_default(),
+                duration: str_from_duration(&e.duration),
+                note: e.note.clone(),
+                ..Default::default()
+            },
+            Event::Diaper(ref e) => RawRecord{
+                typ: "Diaper",
+                start: str_from_datetime(&e.time),
+                extra: match (e.pee, e.poo) {
+                    (true, true) => "Urine, Feces",
+                    (true, false) => "Urine",
+                    (false, true) => "Feces",
+                    (false, false) => "",
+                }.to_string(),
+                // Details read from the note are left to the note.
+                extra2: e.detail.without(&DiaperDetail::from_texts(&[&e.note])).to_string(),
+                note: e.note.clone(),
+                ..Default::default()
+            },
+            Event::Feeding(FeedingEvent::Bottle(ref e)) => RawRecord{
+                typ: "Bottle feeding",
+                start: str_from_datetime(&e.time),
+                extra: volume(&e.volume),
+                extra2: match e.milk {
+                    Milk::BreastMilk => "Mom's milk",
+                    Milk::Formula => "Formula milk",
+                    Milk::Mixed => "Mixed milk",
+                    Milk::Donor => "Donor milk",
+                    Milk::Other(ref label) => label,
+                    Milk::Unknown => "",
+                }.to_string(),
+                note: e.note.clone(),
+                ..Default::default()
+            },
+            Event::Feeding(FeedingEvent::LeftBreast(ref e)) |
+            Event::Feeding(FeedingEvent::RightBreast(ref e)) => RawRecord{
+                typ: match *event {
+                    Event::Feeding(FeedingEvent::LeftBreast(_)) => "Left breast",
+                    _ => "Right breast",
+                },
+                start: str_from_datetime(&e.start),
+                end: e.end.as_ref().map(str_from_datetime).unwrap_or_default(),
+                duration: str_from_duration(&e.duration),
+                note: e.note.clone(),
+                ..Default::default()
+            },
+            Event::Pumping(ref e) => RawRecord{
+                typ: "Pumping",
+                start: str_from_datetime(&e.start),
+                extra: volume(&e.volume),
+                note: e.note.clone(),
+                ..Default::default()
+            },
+            Event::TummyTime(ref e) => RawRecord{
+                typ: "Tummy time",
+                start: str_from_datetime(&e.start),
+                end: e.end.as_ref().map(str_from_datetime).unwrap_or_default(),
+                duration: str_from_duration(&e.duration),
+                note: e.note.clone(),
+                ..Default::default()
+            },
+            Event::Vaccination(ref e) => RawRecord{
+                typ: "Vaccination",
+                start: str_from_datetime(&e.time),
+                extra: e.vaccine.clone(),
+                extra2: e.dose.map(|d| format!("Dose {}", d)).unwrap_or_default(),
+                note: e.note.clone(),
+                ..Default::default()
+            },
+            Event::Meal(ref e) => RawRecord{
+                typ: "Meal",
+                start: str_from_datetime(&e.time),
+                note: e.note.clone(),
+                ..Default::default()
+            },
+            Event::Measure(ref e) => {
+                let mut parts = Vec::new();
+                if let Some(ref w) = e.weight {
+                    parts.push(format!("Weight: {}", mass(w)));
+                }
+                if let Some(ref h) = e.height {
+                    parts.push(format!("Height: {}", length(h)));
+                }
+                if let Some(ref hc) = e.head_circ {
+                    parts.push(format!("Head circumference: {}", length(hc)));
+                }
+                RawRecord{
+                    typ: "Measure",
+                    start: str_from_datetime(&e.time),
+                    extra: parts.join(", "),
+                    note: e.note.clone(),
+                    ..Default::default()
+                }
+            },
+            Event::Note(ref e) => RawRecord{
+                typ: "Note",
+                start: str_from_datetime(&e.time),
+                note: e.note.clone(),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn fields(&self) -> [&str; 7] {
+        [self.typ, &self.start, &self.end, &self.duration, &self.extra, &self.extra2, &self.note]
+    }
+}
+
+fn str_from_datetime(dt: &Timestamp) -> String {
+    dt.format("%d/%m/%Y %H:%M").to_string()
+}
+
+// Writes a quantity to at most two decimal places, e.g. "1.5 oz", as the app
+// does, unless that wouldn't read back as the same quantity.
+fn str_from_quantity<T: PartialEq>(quantity: &T, value: f32, unit: &str, from: fn(f32) -> T) -> Option<String> {
+    let s = format!("{:.2}", value);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if from(s.parse().ok()?) == *quantity {
+        Some(format!("{} {}", s, unit))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,535 @@
+extern crate babystats;
+extern crate chrono;
+extern crate csv;
+
+use babystats::{Alignment,BabyManagerData,Event,FeedingConfig,FeedingEvent,MissingDays,NightConfig,Period,RollingConfig,
+                Schedule,ScheduledDose,SleepEvent,SummaryConfig,VaccinationEvent,Volume};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+const HEADER: &str = "Type,Start,End,Duration,Extra,Extra2,Note\n";
+
+fn parse(rows: &str) -> Vec<Event> {
+    let csv = format!("{}{}", HEADER, rows);
+    let mut rdr = BabyManagerData::from_reader(csv.as_bytes()).with_timezone("UTC".parse().unwrap());
+    rdr.into_iter().map(|e| e.unwrap()).collect()
+}
+
+fn sleeps(rows: &str) -> Vec<SleepEvent> {
+    parse(rows).into_iter().filter_map(|e| match e {
+        Event::Sleep(se) => Some(se),
+        _ => None,
+    }).collect()
+}
+
+#[test]
+fn nights_span_bedtime_to_wake() {
+    let sleeps = sleeps("\
+Sleep,01/03/2018 14:00,01/03/2018 15:00,01:00,,,
+Sleep,01/03/2018 19:30,02/03/2018 00:30,05:00,,,
+Sleep,02/03/2018 00:50,02/03/2018 04:00,03:10,,,
+Sleep,02/03/2018 04:30,02/03/2018 06:30,02:00,,,
+Sleep,02/03/2018 10:00,02/03/2018 11:00,01:00,,,
+");
+    let nights = babystats::nights(&sleeps, &NightConfig::default());
+    assert_eq!(nights.len(), 1);
+    let night = &nights[0];
+    assert_eq!(night.date, chrono::NaiveDate::from_ymd_opt(2018, 3, 1).unwrap());
+    assert_eq!(night.wakeups, 2);
+    assert_eq!(night.wake_windows, vec![chrono::Duration::minutes(20), chrono::Duration::minutes(30)]);
+    assert_eq!(night.total_sleep, chrono::Duration::minutes(610));
+    assert_eq!(night.longest_stretch, chrono::Duration::hours(5));
+}
+
+#[test]
+fn long_gaps_end_the_night() {
+    let sleeps = sleeps("\
+Sleep,01/03/2018 19:30,02/03/2018 00:30,05:00,,,
+Sleep,02/03/2018 03:00,02/03/2018 06:30,03:30,,,
+");
+    let config = NightConfig{
+        max_gap: chrono::Duration::minutes(60),
+        ..Default::default()
+    };
+    let nights = babystats::nights(&sleeps, &config);
+    assert_eq!(nights.len(), 1);
+    assert_eq!(nights[0].wake_time.naive_local().time(), chrono::NaiveTime::from_hms_opt(0, 30, 0).unwrap());
+    assert_eq!(nights[0].total_sleep, chrono::Duration::hours(5));
+}
+
+#[test]
+fn nights_end_early_without_a_morning_wake() {
+    let sleeps = sleeps("\
+Sleep,01/03/2018 18:00,01/03/2018 18:20,00:20,,,catnap
+Sleep,01/03/2018 20:00,02/03/2018 02:30,06:30,,,
+Sleep,02/03/2018 13:00,02/03/2018 14:00,01:00,,,
+");
+    let nights = babystats::nights(&sleeps, &NightConfig::default());
+    assert_eq!(nights.len(), 1);
+    assert_eq!(nights[0].date, date(1));
+    assert_eq!(nights[0].total_sleep, chrono::Duration::minutes(390));
+    assert_eq!(nights[0].wakeups, 0);
+
+    let events = parse("\
+Sleep,01/03/2018 20:00,02/03/2018 02:30,06:30,,,
+Sleep,02/03/2018 13:00,02/03/2018 14:00,01:00,,,
+");
+    let days = babystats::daily_summaries(&events, &SummaryConfig::default());
+    assert_eq!(days[0].night_sleep, chrono::Duration::minutes(390));
+    assert_eq!(days[0].day_sleep, chrono::Duration::zero());
+    assert_eq!(days[1].day_sleep, chrono::Duration::hours(1));
+}
+
+#[test]
+fn sessions_stitch_brief_wakes() {
+    let sleeps = sleeps("\
+Sleep,01/03/2018 13:00,01/03/2018 13:40,00:40,,,
+Sleep,01/03/2018 13:50,01/03/2018 14:30,00:40,,,
+Sleep,01/03/2018 16:00,01/03/2018 16:30,00:30,,,
+");
+    let sessions = babystats::sleep_sessions(&sleeps, chrono::Duration::minutes(15));
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions[0].segments.len(), 2);
+    assert_eq!(sessions[0].wakes(), 1);
+    assert_eq!(sessions[0].span(), chrono::Duration::minutes(90));
+    assert_eq!(sessions[0].sleep(), chrono::Duration::minutes(80));
+
+    let days = babystats::sleep_by_date(&sessions, 5);
+    assert_eq!(days[0].sessions, 2);
+    assert_eq!(days[0].longest, chrono::Duration::minutes(90));
+    assert_eq!(days[0].total, chrono::Duration::minutes(110));
+}
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2018, 3, day).unwrap()
+}
+
+#[test]
+fn rolling_statistics_handle_missing_days() {
+    let series: BTreeMap<NaiveDate, f64> = vec![(date(1), 2.0), (date(3), 4.0), (date(4), 6.0)].into_iter().collect();
+    let mut config = RollingConfig{
+        days: 3,
+        alignment: Alignment::Trailing,
+        missing: MissingDays::Skip,
+    };
+    let points = babystats::rolling(&series, &config);
+    assert_eq!(points.len(), 4);
+    assert_eq!(points[1].value, None);
+    assert_eq!(points[3].count, 2);
+    assert_eq!(points[3].mean, Some(5.0));
+    assert_eq!(points[3].std_dev, Some(1.0));
+
+    config.missing = MissingDays::Zero;
+    let points = babystats::rolling(&series, &config);
+    assert_eq!(points[2].median, Some(2.0));
+    assert_eq!(points[2].min, Some(0.0));
+
+    config.missing = MissingDays::Gap;
+    let points = babystats::rolling(&series, &config);
+    assert_eq!(points[2].mean, None);
+    assert_eq!(points[0].mean, Some(2.0));
+
+    config.alignment = Alignment::Centered;
+    config.missing = MissingDays::Skip;
+    let points = babystats::rolling(&series, &config);
+    assert_eq!(points[2].max, Some(6.0));
+}
+
+#[test]
+fn daily_summary_splits_day_and_night_sleep() {
+    let events = parse("\
+Sleep,01/03/2018 13:00,01/03/2018 14:00,01:00,,,
+Sleep,01/03/2018 19:30,02/03/2018 00:30,05:00,,,
+Sleep,02/03/2018 00:50,02/03/2018 06:00,05:10,,,
+Bottle feeding,02/03/2018 06:30,,,120 ml,Mom's milk,
+Bottle feeding,02/03/2018 09:30,,,60 ml,Formula milk,
+Left breast,02/03/2018 12:00,02/03/2018 12:15,00:15,,,
+Diaper,02/03/2018 07:00,,,Urine Feces,,
+Tummy time,02/03/2018 10:00,,00:10,,,
+Meal,02/03/2018 12:30,,,,,carrots
+");
+    let days = babystats::daily_summaries(&events, &SummaryConfig::default());
+    assert_eq!(days.len(), 2);
+    assert_eq!(days[0].date, date(1));
+    assert_eq!(days[0].day_sleep, chrono::Duration::hours(1));
+    assert_eq!(days[0].night_sleep, chrono::Duration::minutes(610));
+    assert_eq!(days[0].longest_stretch, chrono::Duration::minutes(310));
+    assert_eq!(days[1].feeds, 3);
+    assert_eq!(days[1].breast_milk_volume, Volume::from_ml(120.0));
+    assert_eq!(days[1].formula_volume, Volume::from_ml(60.0));
+    assert_eq!(days[1].left_breast, chrono::Duration::minutes(15));
+    assert_eq!((days[1].wet_diapers, days[1].dirty_diapers), (1, 1));
+    assert_eq!(days[1].tummy_time, chrono::Duration::minutes(10));
+    assert_eq!(days[1].meals, vec!["12:30 carrots".to_string()]);
+}
+
+#[test]
+fn aggregates_by_iso_week() {
+    // 2018-03-04 is a Sunday.
+    let series: BTreeMap<NaiveDate, f64> = vec![(date(3), 4.0), (date(4), 6.0), (date(5), 6.0), (date(7), 9.0)].
+        into_iter().collect();
+    let weeks = babystats::aggregate(&series, &Period::Week);
+    assert_eq!(weeks.len(), 2);
+    assert_eq!(weeks[0].label, "2018-W09");
+    assert_eq!((weeks[0].days, weeks[0].total, weeks[0].mean), (2, 10.0, 5.0));
+    assert_eq!(weeks[0].delta, None);
+    assert_eq!(weeks[1].mean, 7.5);
+    assert_eq!(weeks[1].delta, Some(2.5));
+    assert_eq!(weeks[1].delta_percent, Some(50.0));
+    assert_eq!(babystats::sparkline(&weeks[1].daily), "▁ █");
+
+    let custom: Period = "2018-03-04".parse().unwrap();
+    let periods = babystats::aggregate(&series, &custom);
+    assert_eq!(periods.len(), 1);
+    assert_eq!(periods[0].label, "2018-03-04..2018-03-07");
+}
+
+fn feeding(rows: &str) -> Vec<FeedingEvent> {
+    parse(rows).into_iter().filter_map(|e| match e {
+        Event::Feeding(fe) => Some(fe),
+        _ => None,
+    }).collect()
+}
+
+#[test]
+fn feed_intervals_and_clusters() {
+    let events = feeding("\
+Left breast,01/03/2018 06:00,01/03/2018 06:15,00:15,,,
+Right breast,01/03/2018 06:20,01/03/2018 06:30,00:10,,,
+Bottle feeding,01/03/2018 09:00,,,4 oz,Formula milk,
+Bottle feeding,01/03/2018 18:00,,,2 oz,Formula milk,
+Bottle feeding,01/03/2018 19:00,,,2 oz,Formula milk,
+Left breast,01/03/2018 20:00,01/03/2018 20:10,00:10,,,
+");
+    let config = FeedingConfig{
+        cluster_feeds: 3,
+        cluster_window: chrono::Duration::hours(2),
+        ..Default::default()
+    };
+    let feeds = babystats::feeds(&events, &config);
+    assert_eq!(feeds.len(), 5);
+    assert_eq!(feeds[0].events.len(), 2);
+
+    let intervals = babystats::feed_intervals(&feeds);
+    assert_eq!(intervals[0].interval, chrono::Duration::hours(3));
+    let by_time = babystats::intervals_by_time_of_day(&intervals, 12);
+    assert_eq!(by_time[0].intervals, 2);
+    assert_eq!(by_time[1].mean, Some(chrono::Duration::minutes(60)));
+
+    let clusters = babystats::cluster_feeds(&feeds, &config);
+    assert_eq!(clusters.len(), 1);
+    assert_eq!(clusters[0].feeds, 3);
+
+    let days = babystats::feeding_by_date(&events, &config);
+    assert_eq!(days[0].feeds, 5);
+    assert_eq!(days[0].mean_interval, Some(chrono::Duration::minutes(210)));
+}
+
+#[test]
+fn next_side_alternates_unless_imbalanced() {
+    let mut events = feeding("\
+Left breast,01/03/2018 06:00,01/03/2018 06:20,00:20,,,
+Right breast,01/03/2018 09:00,01/03/2018 09:18,00:18,,,
+");
+    let config = babystats::NextSideConfig::default();
+    let next = babystats::next_side(&events, &config).unwrap();
+    assert_eq!(next.side, babystats::Side::Left);
+    assert!(!next.rebalancing);
+
+    events.extend(feeding("Left breast,01/03/2018 12:00,01/03/2018 12:30,00:30,,,\n\
+                           Right breast,01/03/2018 15:00,01/03/2018 15:05,00:05,,,\n"));
+    let next = babystats::next_side(&events, &config).unwrap();
+    assert_eq!(next.side, babystats::Side::Right);
+    assert!(next.rebalancing);
+
+    let days = babystats::side_balance_by_date(&events);
+    assert_eq!(days[0].left, chrono::Duration::minutes(50));
+    assert_eq!(days[0].right, chrono::Duration::minutes(23));
+}
+
+#[test]
+fn intake_by_milk_type() {
+    let days = babystats::intake_by_date(&feeding("\
+Bottle feeding,01/03/2018 06:00,,,60 ml,Formula milk,
+Bottle feeding,01/03/2018 10:00,,,20 ml,Mom's milk,
+Bottle feeding,01/03/2018 14:00,,,40 ml,Mixed milk,
+Bottle feeding,01/03/2018 18:00,,,30 ml,Goat's milk,
+"));
+    assert_eq!(days.len(), 1);
+    assert_eq!(days[0].bottles, 4);
+    assert!((days[0].other.ml() - 30.0).abs() < 0.01);
+    assert!((days[0].total.ml() - 150.0).abs() < 0.01);
+    assert!((days[0].breast_milk_share.unwrap() - 1.0 / 3.0).abs() < 0.001);
+}
+
+#[test]
+fn pumping_yields_and_mismatches() {
+    let sessions: Vec<_> = parse("\
+Pumping,01/03/2018 06:00,,,120 ml,,60L 58R
+Pumping,01/03/2018 14:00,,,90 ml,,40L 30R
+Pumping,02/03/2018 07:00,,,100 ml,,
+").into_iter().filter_map(|e| match e {
+        Event::Pumping(pe) => Some(pe),
+        _ => None,
+    }).collect();
+
+    let days = babystats::pumping_by_date(&sessions);
+    assert_eq!(days[0].sessions, 2);
+    assert!((days[0].mean_per_session.ml() - 105.0).abs() < 0.01);
+
+    let times = babystats::yield_by_time_of_day(&sessions, 12);
+    assert_eq!(times[0].sessions, 2);
+    assert!((times[0].mean.unwrap().ml() - 110.0).abs() < 0.01);
+
+    let mismatches = babystats::pumping_mismatches(&sessions, babystats::Volume::from_ml(5.0));
+    assert_eq!(mismatches.len(), 1);
+    assert!((mismatches[0].difference.ml() + 20.0).abs() < 0.01);
+}
+
+#[test]
+fn diaper_alerts_for_few_wet_and_no_stool() {
+    let diapers: Vec<_> = parse("\
+Diaper,01/03/2018 06:00,,,\"Urine, Feces\",,
+Diaper,01/03/2018 12:00,,,Urine,,
+Diaper,01/03/2018 18:00,,,Urine,,
+Diaper,02/03/2018 06:00,,,Urine,,
+Diaper,02/03/2018 20:00,,,Urine,,
+Diaper,03/03/2018 06:00,,,Urine,,
+Diaper,05/03/2018 07:00,,,Urine,,
+").into_iter().filter_map(|e| match e {
+        Event::Diaper(de) => Some(de),
+        _ => None,
+    }).collect();
+
+    let days = babystats::diapers_by_date(&diapers);
+    assert_eq!(days[1].longest_wet_gap, Some(chrono::Duration::hours(14)));
+
+    let config = babystats::DiaperConfig{min_wet: 3, ..Default::default()};
+    let alerts = babystats::diaper_alerts(&diapers, &config);
+    assert_eq!(alerts.len(), 3);
+    assert_eq!(alerts[0].kind, babystats::DiaperAlertKind::NoStool);
+    assert_eq!(alerts[1].kind, babystats::DiaperAlertKind::FewWet);
+    assert_eq!(alerts[1].count, 1);
+    assert_eq!(alerts[2].count, 0);
+}
+
+#[test]
+fn stool_summary_and_query() {
+    let diapers: Vec<_> = parse("\
+Diaper,01/03/2018 06:00,,,\"Urine, Feces\",,green seedy
+Diaper,01/03/2018 10:00,,,Urine,,rash
+Diaper,01/03/2018 14:00,,,Feces,,yellow soft
+Diaper,05/03/2018 06:00,,,Feces,,yellow
+").into_iter().filter_map(|e| match e {
+        Event::Diaper(de) => Some(de),
+        _ => None,
+    }).collect();
+
+    let weeks = babystats::stool_summary(&diapers, Some(&babystats::Period::Week));
+    assert_eq!(weeks.len(), 2);
+    assert_eq!(weeks[0].dirty, 2);
+    assert_eq!(weeks[0].rashes, 1);
+    assert_eq!(weeks[0].colors.get(&babystats::StoolColor::Green), Some(&1));
+    assert_eq!(weeks[1].colors.get(&babystats::StoolColor::Yellow), Some(&1));
+
+    let query: babystats::DiaperQuery = "dirty,yellow".parse().unwrap();
+    assert_eq!(diapers.iter().filter(|de| query.matches(de)).count(), 2);
+    assert!("sparkly".parse::<babystats::DiaperQuery>().is_err());
+
+    let rows = babystats::diaper_log(&diapers, &query);
+    let mut wtr = csv::Writer::from_writer(Vec::new());
+    for row in &rows {
+        wtr.serialize(row).unwrap();
+    }
+    let csv = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines, vec![
+        "time,wet,dirty,color,consistency,amount,rash,cream,note",
+        "2018-03-01T14:00:00Z,false,true,yellow,soft,,false,false,yellow soft",
+        "2018-03-05T06:00:00Z,false,true,yellow,,,false,false,yellow",
+    ]);
+}
+
+#[test]
+fn growth_percentiles_and_crossings() {
+    use babystats::{GrowthChart,GrowthMeasure,GrowthReference,Sex};
+
+    assert!((babystats::percentile(0.0) - 50.0).abs() < 1e-6);
+    assert!((babystats::percentile(1.959964) - 97.5).abs() < 1e-3);
+    assert!((babystats::percentile(-1.959964) - 2.5).abs() < 1e-3);
+
+    let cdc = "Sex,Agemos,L,M,S\n1,24,-0.2165,12.7415,0.1082\n1,24.5,-0.2398,12.8810,0.1083\n";
+    let reference = GrowthReference::who().with_cdc_reader(cdc.as_bytes(), GrowthMeasure::Weight).unwrap();
+    let at = |months: f64| reference.lms(Sex::Male, GrowthMeasure::Weight, months).unwrap();
+    assert!((at(24.0).m - 12.1515).abs() < 1e-9);
+    assert!((at(24.5).m - 12.8810).abs() < 1e-9);
+    assert!(reference.lms(Sex::Male, GrowthMeasure::Weight, 25.0).is_none());
+    // The CDC median at 24.5 months is the 50th percentile.
+    assert!((babystats::percentile(at(24.5).z_score(12.8810)) - 50.0).abs() < 1e-6);
+    // Only the WHO tables are built in, up to 24 months.
+    let who = GrowthReference::who();
+    assert_eq!(who.max_age_months(Sex::Female, GrowthMeasure::HeadCirc), Some(24.0));
+    assert!(who.lms(Sex::Male, GrowthMeasure::Weight, 24.5).is_none());
+    assert_eq!(reference.max_age_months(Sex::Male, GrowthMeasure::Weight), Some(24.5));
+
+    let measurements: Vec<_> = parse("\
+Measure,01/03/2018 10:00,,,\"Weight: 3.35 kg, Height: 49.9 cm\",,
+Measure,01/05/2018 10:00,,,\"Weight: 5.6 kg\",,
+Measure,01/07/2018 10:00,,,\"Weight: 6.0 kg\",,
+").into_iter().filter_map(|e| match e {
+        Event::Measure(me) => Some(me),
+        _ => None,
+    }).collect();
+    let chart = GrowthChart{
+        reference: GrowthReference::who(),
+        birth: NaiveDate::from_ymd_opt(2018, 3, 1).unwrap(),
+        sex: Sex::Male,
+    };
+    let rows = babystats::growth_table(&measurements, Some(&chart));
+    assert!(rows[0].weight_z.unwrap().abs() < 0.05);
+    assert!(rows[1].height_z.is_none());
+
+    let crossings = babystats::percentile_crossings(&rows);
+    assert_eq!(crossings.len(), 1);
+    assert_eq!(crossings[0].measure, GrowthMeasure::Weight);
+    assert_eq!(crossings[0].lines, -3);
+}
+
+#[test]
+fn profiles_give_ages_and_corrected_charts() {
+    let profiles = babystats::profiles_from_toml("
+[[child]]
+name = \"Ada\"
+birth = \"2018-02-01\"
+sex = \"female\"
+gestational_weeks = 36
+
+[[child]]
+name = \"Bo\"
+birth = \"2018-02-01\"
+").unwrap();
+    assert_eq!(profiles.len(), 2);
+    let ada = &profiles[0];
+    assert!(ada.is_preterm());
+    assert_eq!(ada.due(), NaiveDate::from_ymd_opt(2018, 3, 1).unwrap());
+    assert_eq!(ada.week_label(NaiveDate::from_ymd_opt(2018, 3, 8).unwrap()), "week 6 (corrected 2)");
+    assert_eq!(ada.growth_chart(babystats::GrowthReference::who()).unwrap().birth, ada.due());
+    assert_eq!(profiles[1].week_label(NaiveDate::from_ymd_opt(2018, 2, 7).unwrap()), "week 1");
+    assert!(profiles[1].growth_chart(babystats::GrowthReference::who()).is_none());
+
+    let one = babystats::profiles_from_json("{\"name\": \"Cy\", \"birth\": \"2018-03-01\", \"sex\": \"male\"}").unwrap();
+    assert_eq!(one[0].sex, Some(babystats::Sex::Male));
+}
+
+#[test]
+fn children_are_filtered_and_compared() {
+    let csv = "Type,Start,End,Duration,Extra,Extra2,Note,Child\n\
+               Sleep,01/03/2018 13:00,01/03/2018 15:00,02:00,,,,Ada\n\
+               Sleep,01/03/2018 13:00,01/03/2018 14:00,01:00,,,,Bo\n\
+               Sleep,02/03/2018 13:00,02/03/2018 14:30,01:30,,,,Bo\n\
+               Note,02/03/2018 16:00,,,,,both,\n";
+    let mut rdr = BabyManagerData::from_reader(csv.as_bytes()).with_timezone("UTC".parse().unwrap());
+    let events: Vec<_> = rdr.child_events().map(|ce| ce.unwrap()).collect();
+    assert_eq!(babystats::children(&events), vec!["Ada".to_string(), "Bo".to_string()]);
+    assert_eq!(babystats::filter_child(events.clone(), "Bo").len(), 3);
+
+    let groups: BTreeMap<String, Vec<Event>> = babystats::group_by_child(events).into_iter().
+        filter_map(|(child, events)| child.map(|c| (c, events))).
+        collect();
+    let comparison = babystats::compare_children(&groups, babystats::Metric::TotalSleep, babystats::UnitSystem::Metric);
+    assert_eq!(comparison.children, vec!["Ada".to_string(), "Bo".to_string()]);
+    assert_eq!(comparison.rows.len(), 2);
+    assert_eq!(comparison.rows[0].values, vec![Some(2.0), Some(1.0)]);
+    assert_eq!(comparison.rows[1].values, vec![None, Some(1.5)]);
+}
+
+#[test]
+fn merge_drops_duplicates_and_flags_near_duplicates() {
+    let phone = parse("\
+Bottle feeding,02/03/2018 06:00,,,4 oz,Formula milk,
+Sleep,02/03/2018 09:00,,01:00,,,nap
+Bottle feeding,02/03/2018 12:00,,,3 oz,Formula milk,
+Note,02/03/2018 15:00,,,,,first note
+");
+    let tablet = parse("\
+Bottle feeding,02/03/2018 06:00,,,4 oz,Formula milk,
+Sleep,02/03/2018 09:02,,01:30,,,nap
+Bottle feeding,02/03/2018 12:20,,,2 oz,Formula milk,
+Note,02/03/2018 15:00,,,,,second note
+");
+    let report = babystats::merge(vec![phone, tablet], chrono::Duration::minutes(5));
+    assert_eq!(report.events_per_source, vec![4, 4]);
+    assert_eq!(report.exact_duplicates, 1);
+    assert_eq!(report.events.len(), 7);
+    // Only the naps differ in duration at nearly the same time; the feeds are
+    // too far apart and the notes differ only in text.
+    assert_eq!(report.near_duplicates.len(), 1);
+    let nd = &report.near_duplicates[0];
+    match (&nd.first, &nd.second) {
+        (Event::Sleep(a), Event::Sleep(b)) => {
+            assert_eq!(a.duration, chrono::Duration::minutes(60));
+            assert_eq!(b.duration, chrono::Duration::minutes(90));
+        },
+        pair => panic!("unexpected near-duplicate {:?}", pair),
+    }
+
+    let wide = babystats::merge(vec![report.events.clone()], chrono::Duration::minutes(30));
+    assert_eq!(wide.near_duplicates.len(), 2);
+
+    // Identical rows within one export are separate events, such as two
+    // bottles made up at once.
+    let twice = parse("\
+Bottle feeding,02/03/2018 06:00,,,4 oz,Formula milk,
+Bottle feeding,02/03/2018 06:00,,,4 oz,Formula milk,
+");
+    let report = babystats::merge(vec![twice.clone()], chrono::Duration::minutes(5));
+    assert_eq!(report.exact_duplicates, 0);
+    assert_eq!(report.events.len(), 2);
+    let report = babystats::merge(vec![twice.clone(), twice[..1].to_vec()], chrono::Duration::minutes(5));
+    assert_eq!(report.exact_duplicates, 1);
+    assert_eq!(report.events.len(), 2);
+}
+
+fn vaccinations(rows: &str) -> Vec<VaccinationEvent> {
+    parse(rows).into_iter().filter_map(|e| match e {
+        Event::Vaccination(ve) => Some(ve),
+        _ => None,
+    }).collect()
+}
+
+#[test]
+fn vaccination_history_against_schedule() {
+    let schedule = Schedule{
+        doses: vec![
+            ScheduledDose{vaccine: "Hib".to_string(), dose: 1, age_months: 2},
+            ScheduledDose{vaccine: "HepB".to_string(), dose: 1, age_months: 0},
+            ScheduledDose{vaccine: "IPV".to_string(), dose: 1, age_months: 2},
+            ScheduledDose{vaccine: "IPV".to_string(), dose: 2, age_months: 4},
+            ScheduledDose{vaccine: "MMR".to_string(), dose: 1, age_months: 12},
+        ],
+        aliases: vec![("Polio".to_string(), vec!["IPV".to_string()])].into_iter().collect(),
+    };
+    let events = vaccinations("\
+Vaccination,01/01/2018 10:00,,,Hep B,,
+Vaccination,05/03/2018 10:00,,,\"DTaP-IPV-Hib, dose 1\",,
+Vaccination,05/03/2018 11:00,,,HibMenCY,,
+");
+    let history = babystats::vaccination_history(&schedule, NaiveDate::from_ymd_opt(2018, 1, 1).unwrap(), &events);
+    let as_of = NaiveDate::from_ymd_opt(2018, 6, 1).unwrap();
+    let rows: Vec<_> = history.rows(as_of).into_iter().map(|r| (r.vaccine, r.dose, r.given, r.days_late)).collect();
+    assert_eq!(rows, vec![
+        ("Hib".to_string(), 1, Some(NaiveDate::from_ymd_opt(2018, 3, 5).unwrap()), Some(4)),
+        ("HepB".to_string(), 1, Some(NaiveDate::from_ymd_opt(2018, 1, 1).unwrap()), Some(0)),
+        ("IPV".to_string(), 1, Some(NaiveDate::from_ymd_opt(2018, 3, 5).unwrap()), Some(4)),
+        // Due on 2018-05-01, so overdue.
+        ("IPV".to_string(), 2, None, Some(31)),
+        // Not due until 2019.
+        ("MMR".to_string(), 1, None, None),
+    ]);
+    // HibMenCY merely contains Hib, so it isn't counted as a Hib dose.
+    let unscheduled: Vec<_> = history.unscheduled.iter().map(|ve| ve.vaccine.as_str()).collect();
+    assert_eq!(unscheduled, vec!["HibMenCY"]);
+
+    assert_eq!(schedule.vaccines_for("polio"), vec!["IPV"]);
+    assert_eq!(Schedule::cdc().vaccines_for("Pentacel"), vec!["DTaP", "IPV", "Hib"]);
+}
@@ -0,0 +1,563 @@
+extern crate babystats;
+extern crate chrono;
+extern crate serde_json;
+
+use babystats::{BabyManagerData,BabyManagerWriter,ChildEvent,Clock,DateOrder,DecimalSeparator,DiaperDetail,DstPolicy,
+                Event,FeedingEvent,ImportConfig,Length,Mass,Milk,ParseMode,StoolAmount,StoolColor,StoolConsistency,
+                TimeZoneSpec,UnitSystem,Volume};
+use chrono::{NaiveDate,NaiveDateTime};
+
+const HEADER: &str = "Type,Start,End,Duration,Extra,Extra2,Note\n";
+
+fn parse(rows: &str) -> Vec<Event> {
+    let csv = format!("{}{}", HEADER, rows);
+    let mut rdr = BabyManagerData::from_reader(csv.as_bytes());
+    rdr.into_iter().map(|e| e.unwrap()).collect()
+}
+
+fn parse_in(row: &str, tz: &str, dst: DstPolicy) -> Result<Event, babystats::Error> {
+    let csv = format!("{}{}", HEADER, row);
+    let mut rdr = BabyManagerData::from_reader(csv.as_bytes())
+        .with_timezone(tz.parse().unwrap())
+        .with_dst_policy(dst);
+    let mut events = rdr.into_iter();
+    events.next().unwrap()
+}
+
+fn parse_with(rows: &str, config: ImportConfig) -> Vec<Event> {
+    let csv = format!("{}{}", HEADER, rows);
+    let mut rdr = BabyManagerData::from_reader(csv.as_bytes()).with_config(config);
+    rdr.into_iter().map(|e| e.unwrap()).collect()
+}
+
+fn parse_one(row: &str) -> Event {
+    let mut events = parse(row);
+    assert_eq!(events.len(), 1);
+    events.pop().unwrap()
+}
+
+fn naive(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+}
+
+// Timestamps are wall-clock times in the local timezone, not UTC.
+#[test]
+fn timestamps_are_local_wall_clock_time() {
+    match parse_one("Sleep,01/03/2018 20:00,02/03/2018 01:30,05:30,,,\n") {
+        Event::Sleep(se) => {
+            assert_eq!(se.start.naive_local(), naive("2018-03-01 20:00"));
+            assert_eq!(se.end.unwrap().naive_local(), naive("2018-03-02 01:30"));
+            assert_eq!(se.start.date_naive(), NaiveDate::from_ymd_opt(2018, 3, 1).unwrap());
+            assert_eq!(se.duration, chrono::Duration::minutes(330));
+        },
+        e => panic!("unexpected event {:?}", e),
+    }
+}
+
+#[test]
+fn timestamps_are_day_month_year() {
+    let e = parse_one("Note,02/03/2018 13:00,,,,,hi\n");
+    assert_eq!(e.time().naive_local(), naive("2018-03-02 13:00"));
+}
+
+#[test]
+fn formula_milk_is_formula() {
+    match parse_one("Bottle feeding,02/03/2018 06:00,,,3.5 oz,Formula milk,\n") {
+        Event::Feeding(FeedingEvent::Bottle(be)) => {
+            assert_eq!(be.milk, Milk::Formula);
+            assert!((be.volume.oz() - 3.5).abs() < 0.001);
+        },
+        e => panic!("unexpected event {:?}", e),
+    }
+}
+
+// Older exports label formula as just "Formula".
+#[test]
+fn bare_formula_is_formula() {
+    match parse_one("Bottle feeding,02/03/2018 06:00,,,3.5 oz,Formula,\n") {
+        Event::Feeding(FeedingEvent::Bottle(be)) => assert_eq!(be.milk, Milk::Formula),
+        e => panic!("unexpected event {:?}", e),
+    }
+}
+
+#[test]
+fn moms_milk_is_breast_milk() {
+    match parse_one("Bottle feeding,02/03/2018 06:00,,,2 oz,Mom's milk,\n") {
+        Event::Feeding(FeedingEvent::Bottle(be)) => assert_eq!(be.milk, Milk::BreastMilk),
+        e => panic!("unexpected event {:?}", e),
+    }
+}
+
+#[test]
+fn richer_milk_labels() {
+    let milk = |label: &str| match parse_one(&format!("Bottle feeding,02/03/2018 06:00,,,2 oz,{},\n", label)) {
+        Event::Feeding(FeedingEvent::Bottle(be)) => be.milk,
+        e => panic!("unexpected event {:?}", e),
+    };
+    assert_eq!(milk("Mixed milk"), Milk::Mixed);
+    assert_eq!(milk("Donor milk"), Milk::Donor);
+    assert_eq!(milk("Goat's milk"), Milk::Other("Goat's milk".to_string()));
+    assert_eq!(milk(""), Milk::Unknown);
+}
+
+#[test]
+fn diaper_details_from_extra_and_note() {
+    match parse_one("Diaper,02/03/2018 06:00,,,\"Urine, Feces\",Green,\"seedy blowout, no rash; zinc cream\"\n") {
+        Event::Diaper(de) => {
+            assert_eq!(de.detail, DiaperDetail{
+                color: Some(StoolColor::Green),
+                consistency: Some(StoolConsistency::Seedy),
+                amount: Some(StoolAmount::Large),
+                rash: false,
+                cream: true,
+            });
+        },
+        e => panic!("unexpected event {:?}", e),
+    }
+}
+
+#[test]
+fn meal_rows_are_meals() {
+    match parse_one("Meal,02/03/2018 12:00,,,,,carrots\n") {
+        Event::Meal(me) => {
+            assert_eq!(me.time.naive_local(), naive("2018-03-02 12:00"));
+            assert_eq!(me.note, "carrots");
+        },
+        e => panic!("unexpected event {:?}", e),
+    }
+}
+
+#[test]
+fn pumping_keeps_left_right_split() {
+    match parse_one("Pumping,02/03/2018 09:00,,,4 oz,,60L 58R\n") {
+        Event::Pumping(pe) => {
+            assert!((pe.volume.oz() - 4.0).abs() < 0.001);
+            assert_eq!(pe.left, Some(Volume::from_ml(60.0)));
+            assert_eq!(pe.right, Some(Volume::from_ml(58.0)));
+        },
+        e => panic!("unexpected event {:?}", e),
+    }
+}
+
+#[test]
+fn pumping_without_split() {
+    match parse_one("Pumping,02/03/2018 09:00,,,4 oz,,\n") {
+        Event::Pumping(pe) => {
+            assert_eq!(pe.left, None);
+            assert_eq!(pe.right, None);
+        },
+        e => panic!("unexpected event {:?}", e),
+    }
+}
+
+#[test]
+fn fixed_offset_timezone() {
+    let e = parse_in("Note,02/03/2018 13:00,,,,,\n", "+05:30", DstPolicy::Error).unwrap();
+    assert_eq!(e.time().to_rfc3339(), "2018-03-02T13:00:00+05:30");
+}
+
+#[test]
+fn named_timezone() {
+    let e = parse_in("Note,02/07/2018 13:00,,,,,\n", "Europe/London", DstPolicy::Error).unwrap();
+    assert_eq!(e.time().to_rfc3339(), "2018-07-02T13:00:00+01:00");
+}
+
+#[test]
+fn timezone_spec_parsing() {
+    assert_eq!("local".parse::<TimeZoneSpec>().unwrap(), TimeZoneSpec::Local);
+    assert!("Not/AZone".parse::<TimeZoneSpec>().is_err());
+}
+
+// 01:30 happened twice in London on 28/10/2018.
+#[test]
+fn ambiguous_times_follow_dst_policy() {
+    let row = "Note,28/10/2018 01:30,,,,,\n";
+    let earliest = parse_in(row, "Europe/London", DstPolicy::Earliest).unwrap();
+    assert_eq!(earliest.time().to_rfc3339(), "2018-10-28T01:30:00+01:00");
+    let latest = parse_in(row, "Europe/London", DstPolicy::Latest).unwrap();
+    assert_eq!(latest.time().to_rfc3339(), "2018-10-28T01:30:00+00:00");
+    match parse_in(row, "Europe/London", DstPolicy::Error) {
+        Err(babystats::Error::BadTimestamp(f)) => assert_eq!(f.column, "Start"),
+        r => panic!("unexpected result {:?}", r),
+    }
+}
+
+// 01:30 never happened in London on 25/03/2018.
+#[test]
+fn nonexistent_times_follow_dst_policy() {
+    let row = "Note,25/03/2018 01:30,,,,,\n";
+    let earliest = parse_in(row, "Europe/London", DstPolicy::Earliest).unwrap();
+    assert_eq!(earliest.time().to_rfc3339(), "2018-03-25T01:30:00+01:00");
+    let latest = parse_in(row, "Europe/London", DstPolicy::Latest).unwrap();
+    assert_eq!(latest.time().to_rfc3339(), "2018-03-25T01:30:00+00:00");
+    assert!(parse_in(row, "Europe/London", DstPolicy::Error).is_err());
+}
+
+const US_ROWS: &str = "\
+Bottle feeding,12/31/2018 6:05 PM,,,3.5 oz,Formula milk,
+Sleep,12/31/2018 11:00 PM,1/1/2019 6:30 AM,07:30,,,
+";
+
+const METRIC_ROWS: &str = "\
+Bottle feeding,31.12.2018 18:05,,,\"118,3 ml\",Formula milk,
+Measure,31.12.2018 19:00,,,\"Weight: 4,5 kg, Height: 55,88 cm\",,
+";
+
+#[test]
+fn us_dates_and_twelve_hour_clock() {
+    let config = ImportConfig{
+        date_order: DateOrder::MonthDayYear,
+        clock: Clock::TwelveHour,
+        ..Default::default()
+    };
+    let events = parse_with(US_ROWS, config);
+    assert_eq!(events[0].time().naive_local(), naive("2018-12-31 18:05"));
+    match events[1] {
+        Event::Sleep(ref se) => assert_eq!(se.end.unwrap().naive_local(), naive("2019-01-01 06:30")),
+        ref e => panic!("unexpected event {:?}", e),
+    }
+}
+
+#[test]
+fn metric_units_and_decimal_comma() {
+    let config = ImportConfig{
+        decimal_separator: DecimalSeparator::Comma,
+        units: UnitSystem::Metric,
+        ..Default::default()
+    };
+    let events = parse_with(METRIC_ROWS, config);
+    assert_eq!(events[0].time().naive_local(), naive("2018-12-31 18:05"));
+    match events[0] {
+        Event::Feeding(FeedingEvent::Bottle(ref be)) => assert!((be.volume.ml() - 118.3).abs() < 0.01),
+        ref e => panic!("unexpected event {:?}", e),
+    }
+    match events[1] {
+        Event::Measure(ref me) => {
+            assert!((me.weight.unwrap().kg() - 4.5).abs() < 0.01);
+            assert!((me.height.unwrap().inches() - 22.0).abs() < 0.01);
+        },
+        ref e => panic!("unexpected event {:?}", e),
+    }
+}
+
+#[test]
+fn detects_us_format() {
+    let csv = format!("{}{}", HEADER, US_ROWS);
+    let config = ImportConfig::detect(csv.as_bytes(), 10).unwrap();
+    assert_eq!(config.date_order, DateOrder::MonthDayYear);
+    assert_eq!(config.clock, Clock::TwelveHour);
+    assert_eq!(config.units, UnitSystem::Imperial);
+}
+
+#[test]
+fn detection_skips_malformed_rows() {
+    let csv = format!("{}Note,12/31/2018 6:05 PM\n{}", HEADER, US_ROWS);
+    let config = ImportConfig::detect(csv.as_bytes(), 10).unwrap();
+    assert_eq!(config.date_order, DateOrder::MonthDayYear);
+    assert_eq!(config.clock, Clock::TwelveHour);
+}
+
+#[test]
+fn detects_metric_format() {
+    let csv = format!("{}{}", HEADER, METRIC_ROWS);
+    let config = ImportConfig::detect(csv.as_bytes(), 10).unwrap();
+    assert_eq!(config.date_order, DateOrder::DayMonthYear);
+    assert_eq!(config.clock, Clock::TwentyFourHour);
+    assert_eq!(config.decimal_separator, DecimalSeparator::Comma);
+    assert_eq!(config.units, UnitSystem::Metric);
+}
+
+#[test]
+fn quantities_in_either_unit() {
+    let events = parse("\
+Bottle feeding,02/03/2018 06:00,,,120 ml,Formula milk,
+Measure,02/03/2018 10:00,,,\"Weight: 7 lb 8 oz, Height: 50 cm, Head circumference: 14 in\",,
+");
+    match events[0] {
+        Event::Feeding(FeedingEvent::Bottle(ref be)) => assert_eq!(be.volume, Volume::from_ml(120.0)),
+        ref e => panic!("unexpected event {:?}", e),
+    }
+    match events[1] {
+        Event::Measure(ref me) => {
+            assert_eq!(me.weight, Some(Mass::from_lb(7.5)));
+            assert_eq!(me.height, Some(Length::from_cm(50.0)));
+            assert_eq!(me.head_circ, Some(Length::from_in(14.0)));
+        },
+        ref e => panic!("unexpected event {:?}", e),
+    }
+}
+
+#[test]
+fn quantities_render_in_either_unit_system() {
+    assert_eq!(Volume::from_oz(4.0).display(UnitSystem::Metric).to_string(), "118 ml");
+    assert_eq!(Mass::from_lb_oz(7.0, 8.0).display(UnitSystem::Imperial).to_string(), "7 lb 8.0 oz");
+    assert_eq!(Mass::from_lb(7.5).display(UnitSystem::Metric).to_string(), "3.40 kg");
+    assert_eq!(Length::from_in(20.0).display(UnitSystem::Metric).to_string(), "50.8 cm");
+}
+
+#[test]
+fn vaccinations_are_not_tummy_time() {
+    let events = parse("\
+Vaccination,02/03/2018 10:00,,,\"DTaP, dose 2\",,left thigh
+Tummy time,02/03/2018 11:00,,00:10,,,
+");
+    match events[0] {
+        Event::Vaccination(ref ve) => {
+            assert_eq!(ve.vaccine, "DTaP");
+            assert_eq!(ve.dose, Some(2));
+            assert_eq!(ve.note, "left thigh");
+        },
+        ref e => panic!("unexpected event {:?}", e),
+    }
+    match events[1] {
+        Event::TummyTime(ref te) => assert_eq!(te.duration, chrono::Duration::minutes(10)),
+        ref e => panic!("unexpected event {:?}", e),
+    }
+}
+
+#[test]
+fn events_are_tagged_with_their_child() {
+    let csv = "Type,Start,End,Duration,Extra,Extra2,Note,Child\n\
+               Note,02/03/2018 13:00,,,,,hi,Ada\n\
+               Note,02/03/2018 14:00,,,,,hi,\n";
+    let mut rdr = BabyManagerData::from_reader(csv.as_bytes()).with_child("Bo");
+    let children: Vec<_> = rdr.child_events().map(|ce| ce.unwrap().child).collect();
+    assert_eq!(children, vec![Some("Ada".to_string()), Some("Bo".to_string())]);
+
+    let csv = format!("{}Note,02/03/2018 13:00,,,,,hi\n", HEADER);
+    let mut rdr = BabyManagerData::from_reader(csv.as_bytes());
+    assert_eq!(rdr.child_events().next().unwrap().unwrap().child, None);
+}
+
+fn parse_err(rows: &str) -> babystats::Error {
+    let csv = format!("{}{}", HEADER, rows);
+    let mut rdr = BabyManagerData::from_reader(csv.as_bytes());
+    let err = rdr.into_iter().find_map(|e| e.err());
+    err.unwrap()
+}
+
+#[test]
+fn errors_locate_the_bad_field() {
+    let err = parse_err("Note,02/03/2018 13:00,,,,,\nSleep,32/03/2018 20:00,,05:30,,,\n");
+    match err {
+        babystats::Error::BadTimestamp(ref f) => {
+            assert_eq!(f.column, "Start");
+            assert_eq!(f.text, "32/03/2018 20:00");
+        },
+        ref e => panic!("unexpected error {:?}", e),
+    }
+    assert_eq!(err.line(), Some(3));
+    assert_eq!(err.to_string(), "bad timestamp at line 3, column Start: \"32/03/2018 20:00\"");
+
+    let err = parse_err("Sleep,01/03/2018 20:00,02/03/2018 01:30,5h,,,\n");
+    match err {
+        babystats::Error::BadDuration(ref f) => assert_eq!(f.column, "Duration"),
+        ref e => panic!("unexpected error {:?}", e),
+    }
+    assert_eq!(err.line(), Some(2));
+    assert_eq!(err.to_string(), "bad duration at line 2, column Duration: \"5h\"; expecting HH:MM");
+
+    let err = parse_err("Sleep,01/03/2018 20:00,31/02/2018 01:30,05:30,,,\n");
+    assert_eq!(err.field().map(|f| f.column), Some("End"));
+
+    let err = parse_err("Bath,02/03/2018 13:00,,,,,\n");
+    match err {
+        babystats::Error::UnknownType(ref f) => assert_eq!(f.text, "Bath"),
+        ref e => panic!("unexpected error {:?}", e),
+    }
+    assert_eq!(err.field().map(|f| f.column), Some("Type"));
+
+    let err = parse_err("Bottle feeding,02/03/2018 06:00,,,3 cups,Formula milk,\n");
+    match err {
+        babystats::Error::BadQuantity(ref f) => assert_eq!((f.line, f.column), (2, "Extra")),
+        ref e => panic!("unexpected error {:?}", e),
+    }
+
+    let err = parse_err("Measure,02/03/2018 10:00,,,Weight: heavy,,\n");
+    match err {
+        babystats::Error::BadMeasurement(ref f) => assert_eq!((f.line, f.column), (2, "Extra")),
+        ref e => panic!("unexpected error {:?}", e),
+    }
+}
+
+const MALFORMED_ROWS: &str = "\
+Note,02/03/2018 13:00,,,,,fine
+Sleep,32/03/2018 20:00,,05:30,,,
+Sleep,01/03/2018 20:00,02/03/2018 01:30,5h,,,
+Bath,02/03/2018 14:00,,,,,
+Note,02/03/2018 15:00,,,
+Note,02/03/2018 16:00,,,,,also fine
+";
+
+#[test]
+fn strict_mode_fails_on_malformed_rows() {
+    let csv = format!("{}{}", HEADER, MALFORMED_ROWS);
+    let mut rdr = BabyManagerData::from_reader(csv.as_bytes());
+    let results: Vec<_> = rdr.into_iter().collect();
+    assert_eq!(results.len(), 6);
+    let lines: Vec<_> = results.iter().filter_map(|r| r.as_ref().err()).map(|e| e.line()).collect();
+    assert_eq!(lines, vec![Some(3), Some(4), Some(5), Some(6)]);
+    assert!(rdr.diagnostics().skipped.is_empty());
+}
+
+#[test]
+fn lenient_mode_skips_malformed_rows() {
+    let csv = format!("{}{}", HEADER, MALFORMED_ROWS);
+    let mut rdr = BabyManagerData::from_reader(csv.as_bytes()).with_mode(ParseMode::Lenient);
+    let notes: Vec<String> = rdr.into_iter().map(|e| match e.unwrap() {
+        Event::Note(ne) => ne.note,
+        e => panic!("unexpected event {:?}", e),
+    }).collect();
+    assert_eq!(notes, vec!["fine", "also fine"]);
+
+    let diagnostics = rdr.diagnostics();
+    assert_eq!(diagnostics.rows_read, 6);
+    assert!((diagnostics.skipped_ratio() - 4.0 / 6.0).abs() < 1e-9);
+    let skipped: Vec<(u64, String)> = diagnostics.skipped.iter().map(|row| (row.line, row.record.join(","))).collect();
+    assert_eq!(skipped, vec![
+        (3, "Sleep,32/03/2018 20:00,,05:30,,,".to_string()),
+        (4, "Sleep,01/03/2018 20:00,02/03/2018 01:30,5h,,,".to_string()),
+        (5, "Bath,02/03/2018 14:00,,,,,".to_string()),
+        (6, "Note,02/03/2018 15:00,,,".to_string()),
+    ]);
+    match diagnostics.skipped[0].error {
+        babystats::Error::BadTimestamp(_) => {},
+        ref e => panic!("unexpected error {:?}", e),
+    }
+    match diagnostics.skipped[3].error {
+        babystats::Error::Csv(_) => {},
+        ref e => panic!("unexpected error {:?}", e),
+    }
+}
+
+#[test]
+fn lenient_mode_keeps_rows_that_are_not_utf8() {
+    let mut csv = HEADER.as_bytes().to_vec();
+    csv.extend_from_slice(b"Note,02/03/2018 13:00,,,,,caf\xe9\n");
+    let mut rdr = BabyManagerData::from_reader(&csv[..]).with_mode(ParseMode::Lenient);
+    assert_eq!(rdr.into_iter().count(), 0);
+    let skipped = &rdr.diagnostics().skipped;
+    assert_eq!(skipped.len(), 1);
+    assert_eq!(skipped[0].line, 2);
+    assert_eq!(skipped[0].record[6], "caf\u{fffd}");
+}
+
+// A row of each event type.
+const ALL_ROWS: &str = "\
+Sleep,01/03/2018 20:00,02/03/2018 01:30,05:30,,,night
+Sleep,02/03/2018 13:00,,01:00,,,
+Diaper,02/03/2018 06:00,,,\"Urine, Feces\",Green,seedy rash
+Bottle feeding,02/03/2018 06:00,,,3.5 oz,Formula milk,
+Bottle feeding,02/03/2018 09:00,,,120 ml,Goat's milk,
+Left breast,02/03/2018 07:00,02/03/2018 07:15,00:15,,,
+Right breast,02/03/2018 07:15,,00:10,,,
+Pumping,02/03/2018 09:00,,,103.509 ml,,60L 58R
+Tummy time,02/03/2018 11:00,02/03/2018 11:10,00:10,,,
+Vaccination,02/03/2018 10:00,,,\"DTaP, dose 2\",,left thigh
+Meal,02/03/2018 12:00,,,,,carrots
+Measure,02/03/2018 10:00,,,\"Weight: 7 lb 8 oz, Height: 50 cm\",,
+Measure,02/03/2018 11:00,,,\"Weight: 3.4019 kg, Head circumference: 13.7 in\",,
+Note,02/03/2018 13:00,,,,,hi
+";
+
+#[test]
+fn events_round_trip_through_json() {
+    let events = parse(ALL_ROWS);
+    assert!(events.iter().any(|e| match *e {
+        Event::Feeding(FeedingEvent::Bottle(ref be)) => be.milk == Milk::Other("Goat's milk".to_string()),
+        _ => false,
+    }));
+    for event in &events {
+        let json = serde_json::to_string(event).unwrap();
+        let back: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(&back, event, "{}", json);
+    }
+}
+
+#[test]
+fn events_round_trip_through_the_writer() {
+    let events = parse(ALL_ROWS);
+    for &units in &[UnitSystem::Imperial, UnitSystem::Metric] {
+        let mut wtr = BabyManagerWriter::from_writer(Vec::new()).with_units(units);
+        for event in &events {
+            wtr.write_event(event).unwrap();
+        }
+        let csv = wtr.into_inner().unwrap();
+        let config = ImportConfig{units, ..Default::default()};
+        let mut rdr = BabyManagerData::from_reader(&csv[..]).with_config(config);
+        let back: Vec<Event> = rdr.into_iter().map(|e| e.unwrap()).collect();
+        assert_eq!(back, events, "{}", String::from_utf8_lossy(&csv));
+    }
+}
+
+#[test]
+fn the_writer_writes_quantities_as_the_app_does() {
+    let mut wtr = BabyManagerWriter::from_writer(Vec::new());
+    for &oz in &[1.5, 5.25, 9.25, 10.5, 18.5] {
+        wtr.write_event(&parse_one(&format!("Bottle feeding,02/03/2018 06:00,,,{} oz,Formula milk,\n", oz))).unwrap();
+    }
+    wtr.write_event(&parse_one("Pumping,02/03/2018 07:00,,,103.5 ml,,\n")).unwrap();
+    wtr.write_event(&parse_one("Measure,02/03/2018 08:00,,,\"Weight: 7.5 lb, Height: 21.25 in\",,\n")).unwrap();
+    let written = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
+    let extras: Vec<&str> = written.lines().skip(1).map(|line| line.split(',').nth(4).unwrap()).collect();
+    assert_eq!(extras, ["1.5 oz", "5.25 oz", "9.25 oz", "10.5 oz", "18.5 oz", "103.5 ml", "\"Weight: 7.5 lb"]);
+    assert!(written.contains("\"Weight: 7.5 lb, Height: 21.25 in\""), "{}", written);
+}
+
+#[test]
+fn the_writer_leaves_diaper_details_to_the_note() {
+    let rows = ["Diaper,02/03/2018 06:00,,,Feces,,green\n", "Diaper,02/03/2018 07:00,,,Feces,Yellow,\"seedy, green\"\n"];
+    let mut wtr = BabyManagerWriter::from_writer(Vec::new());
+    let events: Vec<Event> = rows.iter().map(|row| parse_one(row)).collect();
+    for event in &events {
+        wtr.write_event(event).unwrap();
+    }
+    let written = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
+    assert_eq!(written.lines().skip(1).collect::<Vec<_>>(), [
+        "Diaper,02/03/2018 06:00,,,Feces,,green",
+        "Diaper,02/03/2018 07:00,,,Feces,yellow,\"seedy, green\"",
+    ]);
+    assert_eq!(parse(written.split_once('\n').unwrap().1), events);
+}
+
+#[test]
+fn only_blank_volumes_are_no_volume() {
+    match parse_one("Bottle feeding,02/03/2018 06:00,,,,Formula milk,\n") {
+        Event::Feeding(FeedingEvent::Bottle(be)) => assert_eq!(be.volume, Volume::default()),
+        e => panic!("unexpected event {:?}", e),
+    }
+    for extra in &["abc oz", "4 cups", "four ounces"] {
+        match parse_err(&format!("Bottle feeding,02/03/2018 06:00,,,{},Formula milk,\n", extra)) {
+            babystats::Error::BadQuantity(ref f) => assert_eq!(f.text, *extra),
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+}
+
+#[test]
+fn children_round_trip_through_the_writer() {
+    let csv = "Type,Start,End,Duration,Extra,Extra2,Note,Child\n\
+               Note,02/03/2018 13:00,,,,,hi,Ada\n\
+               Bottle feeding,02/03/2018 14:00,,,4 oz,Formula milk,,Bo\n\
+               Note,02/03/2018 15:00,,,,,both,\n";
+    let mut rdr = BabyManagerData::from_reader(csv.as_bytes());
+    let events: Vec<ChildEvent> = rdr.child_events().map(|ce| ce.unwrap()).collect();
+
+    let mut wtr = BabyManagerWriter::from_writer(Vec::new()).with_child_column();
+    for ce in &events {
+        wtr.write_child_event(ce).unwrap();
+    }
+    let written = wtr.into_inner().unwrap();
+    let mut rdr = BabyManagerData::from_reader(&written[..]);
+    let back: Vec<ChildEvent> = rdr.child_events().map(|ce| ce.unwrap()).collect();
+    assert_eq!(back, events);
+
+    let json = serde_json::to_string(&events).unwrap();
+    assert_eq!(serde_json::from_str::<Vec<ChildEvent>>(&json).unwrap(), events);
+
+    // Without the column, exports keep Baby Manager's layout.
+    let mut wtr = BabyManagerWriter::from_writer(Vec::new());
+    wtr.write_child_event(&events[0]).unwrap();
+    let written = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
+    assert_eq!(written.lines().next(), Some(HEADER.trim_end()));
+}